@@ -0,0 +1,159 @@
+//! Compares monsters each running their own A* search toward the player
+//! against all of them reading one shared flow field.
+//!
+//! Run with `cargo bench --bench pathfinding`.
+
+extern crate rltk;
+use rltk::{Algorithm2D, BaseMap, Point, RandomNumberGenerator};
+use std::time::{Duration, Instant};
+
+#[allow(dead_code)]
+#[path = "../src/flow_field.rs"]
+mod flow_field;
+use flow_field::FlowField;
+
+const WIDTH: i32 = 80;
+const HEIGHT: i32 = 43;
+const MONSTERS: usize = 60;
+const TURNS: u32 = 100;
+
+/// An open level scattered with pillars, so paths have to bend a little
+struct Level {
+    walls: Vec<bool>,
+}
+
+impl Level {
+    fn new(rng: &mut RandomNumberGenerator) -> Level {
+        let mut walls = vec![false; (WIDTH * HEIGHT) as usize];
+        for x in 0..WIDTH {
+            walls[x as usize] = true;
+            walls[((HEIGHT - 1) * WIDTH + x) as usize] = true;
+        }
+        for y in 0..HEIGHT {
+            walls[(y * WIDTH) as usize] = true;
+            walls[(y * WIDTH + WIDTH - 1) as usize] = true;
+        }
+        for _ in 0..400 {
+            let x = rng.range(1, WIDTH - 1);
+            let y = rng.range(1, HEIGHT - 1);
+            walls[(y * WIDTH + x) as usize] = true;
+        }
+        Level { walls }
+    }
+
+    fn open_tile(&self, rng: &mut RandomNumberGenerator) -> usize {
+        loop {
+            let idx = rng.range(0, WIDTH * HEIGHT) as usize;
+            if !self.walls[idx] {
+                return idx;
+            }
+        }
+    }
+}
+
+impl BaseMap for Level {
+    fn is_opaque(&self, idx: usize) -> bool {
+        self.walls[idx]
+    }
+
+    fn get_available_exits(&self, idx: usize) -> Vec<(usize, f32)> {
+        let mut exits = Vec::new();
+        let x = idx as i32 % WIDTH;
+        let y = idx as i32 / WIDTH;
+        for (dx, dy, cost) in [
+            (-1, 0, 1.0),
+            (1, 0, 1.0),
+            (0, -1, 1.0),
+            (0, 1, 1.0),
+            (-1, -1, 1.45),
+            (1, -1, 1.45),
+            (-1, 1, 1.45),
+            (1, 1, 1.45),
+        ]
+        .iter()
+        {
+            let exit = ((y + dy) * WIDTH + x + dx) as usize;
+            if !self.walls[exit] {
+                exits.push((exit, *cost));
+            }
+        }
+        exits
+    }
+
+    fn get_pathing_distance(&self, idx1: usize, idx2: usize) -> f32 {
+        let p1 = Point::new(idx1 as i32 % WIDTH, idx1 as i32 / WIDTH);
+        let p2 = Point::new(idx2 as i32 % WIDTH, idx2 as i32 / WIDTH);
+        rltk::DistanceAlg::Pythagoras.distance2d(p1, p2)
+    }
+}
+
+impl Algorithm2D for Level {
+    fn dimensions(&self) -> Point {
+        Point::new(WIDTH, HEIGHT)
+    }
+}
+
+/// Every monster searches its own path, as the AI used to
+fn per_monster_a_star(level: &Level, player: usize, monsters: &[usize]) -> usize {
+    let mut moved = 0;
+    for monster in monsters.iter() {
+        let path = rltk::a_star_search(*monster, player, level);
+        if path.success && path.steps.len() > 1 {
+            moved += 1;
+        }
+    }
+    moved
+}
+
+/// Both fields are built for the turn, as the game does, and every monster
+/// just reads one
+fn shared_flow_field(level: &Level, player: usize, monsters: &[usize]) -> usize {
+    let toward = FlowField::toward((WIDTH * HEIGHT) as usize, &[player], level);
+    std::hint::black_box(FlowField::away(&toward, level));
+    monsters
+        .iter()
+        .filter(|monster| toward.next_step(**monster, level).is_some())
+        .count()
+}
+
+fn time_turns(
+    name: &str,
+    level: &Level,
+    turns: &[(usize, Vec<usize>)],
+    pathfind: fn(&Level, usize, &[usize]) -> usize,
+) -> Duration {
+    let start = Instant::now();
+    let mut moved = 0;
+    for (player, monsters) in turns.iter() {
+        moved += pathfind(level, *player, monsters);
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{:<20} {:>10.3?} total, {:>10.3?} per turn ({} monster moves)",
+        name,
+        elapsed,
+        elapsed / TURNS,
+        moved
+    );
+    elapsed
+}
+
+fn main() {
+    let mut rng = RandomNumberGenerator::seeded(42);
+    let level = Level::new(&mut rng);
+    let turns: Vec<(usize, Vec<usize>)> = (0..TURNS)
+        .map(|_| {
+            let player = level.open_tile(&mut rng);
+            let monsters = (0..MONSTERS).map(|_| level.open_tile(&mut rng)).collect();
+            (player, monsters)
+        })
+        .collect();
+
+    println!("{} monsters over {} turns", MONSTERS, TURNS);
+    let a_star = time_turns("per-monster A*", &level, &turns, per_monster_a_star);
+    let field = time_turns("shared flow field", &level, &turns, shared_flow_field);
+    println!(
+        "flow field is {:.1}x faster",
+        a_star.as_secs_f64() / field.as_secs_f64()
+    );
+}
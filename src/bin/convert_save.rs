@@ -0,0 +1,54 @@
+//! Converts a save between JSON and the binary format, so binary saves can
+//! still be read and edited by hand.
+//!
+//! Usage: convert_save <json|binary> [--compress] <input> <output>
+
+use std::path::Path;
+use std::process;
+
+#[allow(dead_code)]
+#[path = "../save_format.rs"]
+mod save_format;
+use save_format::{Encoding, Format};
+
+fn usage() -> ! {
+    eprintln!("usage: convert_save <json|binary> [--compress] <input> <output>");
+    process::exit(2);
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let compressed = args.iter().any(|arg| arg == "--compress");
+    let args: Vec<&String> = args.iter().filter(|arg| *arg != "--compress").collect();
+    if args.len() != 3 {
+        usage();
+    }
+
+    let encoding = match args[0].as_str() {
+        "json" => Encoding::Json,
+        "binary" => Encoding::Binary,
+        _ => usage(),
+    };
+    let format = Format {
+        encoding,
+        compressed,
+    };
+
+    let save = save_format::read(Path::new(args[1])).unwrap_or_else(|e| {
+        eprintln!("Unable to read {}: {}", args[1], e);
+        process::exit(1);
+    });
+    // Pretty print JSON, since the point is to read it
+    let written = if encoding == Encoding::Json && !compressed {
+        serde_json::to_string_pretty(&save)
+            .map_err(save_format::FormatError::from)
+            .and_then(|text| std::fs::write(args[2], text).map_err(save_format::FormatError::from))
+    } else {
+        save_format::write(Path::new(args[2]), &save, format)
+    };
+    if let Err(e) = written {
+        eprintln!("Unable to write {}: {}", args[2], e);
+        process::exit(1);
+    }
+    println!("Wrote {} as {}", args[2], format);
+}
@@ -34,6 +34,44 @@ pub struct Viewshed {
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct Monster {}
 
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug)]
+pub enum AiProfile {
+    /// Fights to the death
+    Aggressive,
+    /// Runs once badly hurt
+    Cowardly,
+    /// Calls the rest of the pack when it spots the player
+    Pack,
+    /// Never leaves its post, but fights anything that comes close
+    Stationary,
+}
+
+impl AiProfile {
+    /// Share of its max hp below which a monster runs away
+    pub fn flee_below(self) -> f32 {
+        match self {
+            AiProfile::Cowardly => 0.5,
+            AiProfile::Pack => 0.25,
+            AiProfile::Aggressive | AiProfile::Stationary => 0.0,
+        }
+    }
+}
+
+/// How a monster behaves, and where it last saw the player
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct MonsterBrain {
+    pub profile: AiProfile,
+    pub last_seen_player: Option<rltk::Point>,
+}
+
+/// Where an entity waits while the player is on a different level
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct OtherLevelPosition {
+    pub x: i32,
+    pub y: i32,
+    pub depth: i32,
+}
+
 #[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct Name {
     pub name: String,
@@ -55,24 +93,56 @@ pub struct WantsToMelee {
     pub target: Entity,
 }
 
+/// Damage waiting to be applied, each hit noting whether the player dealt it
+/// and what it came from
 #[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct SufferDamage {
-    pub amount: Vec<i32>,
+    pub amount: Vec<(i32, bool, String)>,
 }
 
 impl SufferDamage {
-    pub fn new_damage(store: &mut WriteStorage<SufferDamage>, victim: Entity, amount: i32) {
+    pub fn new_damage(
+        store: &mut WriteStorage<SufferDamage>,
+        victim: Entity,
+        amount: i32,
+        from_player: bool,
+        cause: &str,
+    ) {
         if let Some(suffering) = store.get_mut(victim) {
-            suffering.amount.push(amount);
+            suffering
+                .amount
+                .push((amount, from_player, cause.to_string()));
         } else {
             let dmg = SufferDamage {
-                amount: vec![amount],
+                amount: vec![(amount, from_player, cause.to_string())],
             };
             store.insert(victim, dmg).expect("Unable to insert damage");
         }
     }
 }
 
+/// Marks an entity the player dealt the killing blow to, so the kill can
+/// be credited when it is removed
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct SlainByPlayer {}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Experience {
+    pub level: i32,
+    pub xp: i32,
+}
+
+impl Experience {
+    /// Experience needed to go from the current level to the next
+    pub fn xp_to_next_level(&self) -> i32 {
+        self.level * 100
+    }
+
+    pub fn level_up_due(&self) -> bool {
+        self.xp >= self.xp_to_next_level()
+    }
+}
+
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct Item {}
 
@@ -94,9 +164,145 @@ pub struct AreaOfEffect {
     pub radius: i32,
 }
 
-#[derive(Component, Debug, ConvertSaveload, Clone)]
-pub struct Confusion {
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug)]
+pub enum StatusEffectType {
+    Poison,
+    Regeneration,
+    Haste,
+    Slow,
+    Blindness,
+    Paralysis,
+    Confusion,
+    Clairvoyance,
+}
+
+impl StatusEffectType {
+    pub fn name(self) -> &'static str {
+        match self {
+            StatusEffectType::Poison => "Poisoned",
+            StatusEffectType::Regeneration => "Regenerating",
+            StatusEffectType::Haste => "Hasted",
+            StatusEffectType::Slow => "Slowed",
+            StatusEffectType::Blindness => "Blind",
+            StatusEffectType::Paralysis => "Paralyzed",
+            StatusEffectType::Confusion => "Confused",
+            StatusEffectType::Clairvoyance => "Clairvoyant",
+        }
+    }
+
+    /// The effect that cancels this one out when applied, if any
+    fn opposite(self) -> Option<StatusEffectType> {
+        match self {
+            StatusEffectType::Haste => Some(StatusEffectType::Slow),
+            StatusEffectType::Slow => Some(StatusEffectType::Haste),
+            _ => None,
+        }
+    }
+}
+
+/// A timed effect. Potency is the damage or healing per turn for poison and
+/// regeneration, and unused by the rest.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug)]
+pub struct StatusEffect {
+    pub kind: StatusEffectType,
     pub turns: i32,
+    #[serde(default)]
+    pub potency: i32,
+}
+
+/// Every effect currently on an entity
+#[derive(Component, Debug, Serialize, Deserialize, Clone, Default)]
+pub struct StatusEffects {
+    pub effects: Vec<StatusEffect>,
+}
+
+impl StatusEffects {
+    /// Adds an effect to the victim. Effects of the same kind don't stack
+    /// up as separate entries: poison adds its potency to what is already
+    /// there, everything else keeps the stronger potency. Either way the
+    /// longer of the two durations is kept. Haste and slow cancel out.
+    pub fn apply(store: &mut WriteStorage<StatusEffects>, victim: Entity, effect: StatusEffect) {
+        if store.get(victim).is_none() {
+            store
+                .insert(victim, StatusEffects::default())
+                .expect("Unable to insert status effects");
+        }
+        let status = store.get_mut(victim).unwrap();
+
+        if let Some(opposite) = effect.kind.opposite() {
+            if status.has(opposite) {
+                status.effects.retain(|e| e.kind != opposite);
+                return;
+            }
+        }
+
+        match status.effects.iter_mut().find(|e| e.kind == effect.kind) {
+            Some(existing) => {
+                existing.turns = i32::max(existing.turns, effect.turns);
+                if effect.kind == StatusEffectType::Poison {
+                    existing.potency += effect.potency;
+                } else {
+                    existing.potency = i32::max(existing.potency, effect.potency);
+                }
+            }
+            None => status.effects.push(effect),
+        }
+    }
+
+    pub fn has(&self, kind: StatusEffectType) -> bool {
+        self.effects.iter().any(|e| e.kind == kind)
+    }
+}
+
+/// A door. Closed doors block sight, and anything walking into one opens
+/// it instead of moving.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Door {
+    pub open: bool,
+}
+
+impl Door {
+    pub fn glyph(&self) -> u8 {
+        if self.open {
+            rltk::to_cp437('/')
+        } else {
+            rltk::to_cp437('+')
+        }
+    }
+}
+
+/// Not shown to the player until found
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Hidden {}
+
+/// Goes off when something steps onto it
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct EntryTrigger {}
+
+/// Used up the first time it goes off
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct SingleActivation {}
+
+/// Marks an entity that moved this turn, so entry triggers can react
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct EntityMoved {}
+
+/// Gained every tick according to speed and spent to take a turn
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Energy {
+    pub speed: i32,
+    pub energy: i32,
+}
+
+/// Marks a monster that has a turn to take this tick
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct MyTurn {}
+
+/// Effects passed on by using an item, triggering a trap or being hit by
+/// a monster
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct AppliesStatus {
+    pub effects: Vec<StatusEffect>,
 }
 
 #[derive(Component, Debug, ConvertSaveload, Clone)]
@@ -126,10 +332,11 @@ pub struct WantsToDropItem {
     pub item: Entity,
 }
 
-#[derive(PartialEq, Copy, Clone, Serialize, Deserialize)]
+#[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
 pub enum EquipmentSlot {
     Melee,
     Shield,
+    Ranged,
 }
 
 #[derive(Component, Serialize, Deserialize, Clone)]
@@ -143,9 +350,12 @@ pub struct Equipped {
     pub slot: EquipmentSlot,
 }
 
-#[derive(Component, ConvertSaveload, Clone)]
-pub struct MeleePowerBonus {
-    pub power: i32,
+/// Damage dice for a weapon, or a monster's claws and teeth, such as 1d6+1
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct MeleeWeapon {
+    pub n_dice: i32,
+    pub die_type: i32,
+    pub bonus: i32,
 }
 
 #[derive(Component, ConvertSaveload, Clone)]
@@ -153,6 +363,29 @@ pub struct DefenseBonus {
     pub defense: i32,
 }
 
+/// A bow or similar launcher, or a monster's natural ranged attack. Ammo
+/// is the name of the item fired; without one the attack needs no ammo.
+/// Damage is rolled on dice, as for a melee weapon.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct RangedWeapon {
+    pub range: i32,
+    pub n_dice: i32,
+    pub die_type: i32,
+    pub bonus: i32,
+    pub ammo: Option<String>,
+}
+
+/// A stack of ammunition. Stacks with the same name merge when picked up.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Ammunition {
+    pub count: i32,
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct WantsToShoot {
+    pub target: rltk::Point,
+}
+
 // Serialization helper code. We need to implement ConvertSaveload for each type that contains an
 // Entity.
 
@@ -162,6 +395,21 @@ pub struct SerializeMe;
 #[derive(Component, Serialize, Deserialize, Clone)]
 pub struct SerializationHelper {
     pub map: super::map::Map,
+    /// None for saves from before runs kept their seed
+    #[serde(default)]
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub dungeon: super::dungeon::MasterDungeonMap,
+    #[serde(default)]
+    pub identification: super::identification::IdentificationTable,
+    #[serde(default)]
+    pub log: super::gamelog::GameLog,
+    #[serde(default)]
+    pub mode: super::saveload_system::GameMode,
+    #[serde(default)]
+    pub stats: super::morgue::RunStats,
+    #[serde(default)]
+    pub rng_seed: Option<u64>,
 }
 
 #[derive(Component, Debug, ConvertSaveload, Clone)]
@@ -169,6 +417,33 @@ pub struct WantsToUnequipItem {
     pub item: Entity,
 }
 
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug)]
+pub enum HungerState {
+    WellFed,
+    Normal,
+    Hungry,
+    Starving,
+}
+
+#[derive(Component, Serialize, Deserialize, Clone)]
+pub struct HungerClock {
+    pub state: HungerState,
+    pub duration: i32,
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct ProvidesFood {}
+
+/// Reveals the whole level when used
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct MagicMapper {}
+
+/// Lets the user sense every monster on the level for a number of turns
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct ProvidesClairvoyance {
+    pub turns: i32,
+}
+
 #[derive(Component, Serialize, Deserialize, Clone)]
 pub struct ParticleLifetime {
     pub lifetime_ms: f32,
@@ -0,0 +1,92 @@
+use super::{Map, OtherLevelPosition, ParticleLifetime, Position, Viewshed, MAPCOUNT};
+use serde::{Deserialize, Serialize};
+use specs::prelude::*;
+use std::collections::HashMap;
+
+/// Every level the player has visited, keyed by depth. The level the
+/// player is on lives in the `Map` resource and is stored here when they
+/// leave it.
+#[derive(Default, Serialize, Deserialize, Clone)]
+pub struct MasterDungeonMap {
+    maps: HashMap<i32, Map>,
+}
+
+impl MasterDungeonMap {
+    pub fn new() -> MasterDungeonMap {
+        MasterDungeonMap {
+            maps: HashMap::new(),
+        }
+    }
+
+    pub fn store_map(&mut self, map: &Map) {
+        self.maps.insert(map.depth, map.clone());
+    }
+
+    /// A previously visited level, ready to be put back into play
+    pub fn get_map(&self, depth: i32) -> Option<Map> {
+        self.maps.get(&depth).map(|map| {
+            let mut map = map.clone();
+            map.tile_content = vec![Vec::new(); MAPCOUNT];
+            for tile in map.visible_tiles.iter_mut() {
+                *tile = false;
+            }
+            map
+        })
+    }
+}
+
+/// Takes everything on the current level except the player out of play by
+/// swapping its `Position` for an `OtherLevelPosition`. Particles are
+/// short lived, so they are simply removed.
+pub fn freeze_level_entities(ecs: &mut World) {
+    let depth = ecs.fetch::<Map>().depth;
+    let entities = ecs.entities();
+    let mut positions = ecs.write_storage::<Position>();
+    let mut other_level_positions = ecs.write_storage::<OtherLevelPosition>();
+    let particles = ecs.read_storage::<ParticleLifetime>();
+    let player_entity = ecs.fetch::<Entity>();
+
+    let mut to_freeze: Vec<(Entity, i32, i32)> = Vec::new();
+    for (entity, pos) in (&entities, &positions).join() {
+        if entity == *player_entity {
+            continue;
+        }
+        if particles.get(entity).is_some() {
+            entities.delete(entity).expect("Unable to delete particle");
+        } else {
+            to_freeze.push((entity, pos.x, pos.y));
+        }
+    }
+
+    for (entity, x, y) in to_freeze {
+        other_level_positions
+            .insert(entity, OtherLevelPosition { x, y, depth })
+            .expect("Unable to insert other level position");
+        positions.remove(entity);
+    }
+}
+
+/// Puts everything that was left on the given level back into play
+pub fn thaw_level_entities(ecs: &mut World, depth: i32) {
+    let entities = ecs.entities();
+    let mut positions = ecs.write_storage::<Position>();
+    let mut other_level_positions = ecs.write_storage::<OtherLevelPosition>();
+    let mut viewsheds = ecs.write_storage::<Viewshed>();
+
+    let mut to_thaw: Vec<(Entity, i32, i32)> = Vec::new();
+    for (entity, pos) in (&entities, &other_level_positions).join() {
+        if pos.depth == depth {
+            to_thaw.push((entity, pos.x, pos.y));
+        }
+    }
+
+    for (entity, x, y) in to_thaw {
+        positions
+            .insert(entity, Position { x, y })
+            .expect("Unable to insert position");
+        other_level_positions.remove(entity);
+        if let Some(viewshed) = viewsheds.get_mut(entity) {
+            viewshed.dirty = true;
+        }
+    }
+}
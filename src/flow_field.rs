@@ -0,0 +1,186 @@
+use rltk::BaseMap;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// Value of tiles that no path reaches
+pub const UNREACHABLE: f32 = f32::MAX;
+
+/// How much further than the goal itself fleeing tries to get. Its magnitude
+/// is above 1, so running past the goal toward open space beats hiding in a
+/// nearby corner.
+const FLEE_WEIGHT: f32 = -1.2;
+
+/// Cost of reaching every tile of a map from the nearest goal. Anything
+/// following the field steps to whichever neighbour has the lowest value,
+/// so one field serves every monster heading for the same place.
+pub struct FlowField {
+    pub values: Vec<f32>,
+}
+
+impl FlowField {
+    /// Field leading toward the nearest of the goals
+    pub fn toward(tile_count: usize, goals: &[usize], map: &dyn BaseMap) -> FlowField {
+        let mut values = vec![UNREACHABLE; tile_count];
+        for goal in goals.iter() {
+            values[*goal] = 0.0;
+        }
+        relax(&mut values, map);
+        FlowField { values }
+    }
+
+    /// Field leading away from the goals of another field. Following it
+    /// heads for wherever is furthest away, rather than just the next tile
+    /// over.
+    pub fn away(toward: &FlowField, map: &dyn BaseMap) -> FlowField {
+        let mut values: Vec<f32> = toward
+            .values
+            .iter()
+            .map(|value| {
+                if *value == UNREACHABLE {
+                    UNREACHABLE
+                } else {
+                    value * FLEE_WEIGHT
+                }
+            })
+            .collect();
+        relax(&mut values, map);
+        FlowField { values }
+    }
+
+    /// The neighbour to step to from the given tile, if any of them is an
+    /// improvement on staying put
+    pub fn next_step(&self, idx: usize, map: &dyn BaseMap) -> Option<usize> {
+        map.get_available_exits(idx)
+            .into_iter()
+            .map(|(exit, _cost)| exit)
+            .filter(|exit| self.values[*exit] < self.values[idx])
+            .min_by(|a, b| self.values[*a].partial_cmp(&self.values[*b]).unwrap())
+    }
+}
+
+/// A tile waiting to be expanded, ordered so the cheapest comes out of the
+/// heap first
+struct Frontier {
+    idx: usize,
+    cost: f32,
+}
+
+impl PartialEq for Frontier {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost
+    }
+}
+
+impl Eq for Frontier {}
+
+impl PartialOrd for Frontier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Frontier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .cost
+            .partial_cmp(&self.cost)
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+/// Spreads the seeded values out over the map, so every tile ends up with
+/// the cheapest cost of reaching it from any seed
+fn relax(values: &mut [f32], map: &dyn BaseMap) {
+    let mut open: BinaryHeap<Frontier> = values
+        .iter()
+        .enumerate()
+        .filter(|(_idx, value)| **value != UNREACHABLE)
+        .map(|(idx, value)| Frontier { idx, cost: *value })
+        .collect();
+
+    while let Some(Frontier { idx, cost }) = open.pop() {
+        // A cheaper way here was already found
+        if cost > values[idx] {
+            continue;
+        }
+        for (exit, step_cost) in map.get_available_exits(idx) {
+            let exit_cost = cost + step_cost;
+            if exit_cost < values[exit] {
+                values[exit] = exit_cost;
+                open.push(Frontier {
+                    idx: exit,
+                    cost: exit_cost,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rltk::{Algorithm2D, Point};
+
+    /// An open room with no walls inside, moved through in four directions
+    struct Room {
+        width: i32,
+        height: i32,
+    }
+
+    impl Algorithm2D for Room {
+        fn dimensions(&self) -> Point {
+            Point::new(self.width, self.height)
+        }
+    }
+
+    impl BaseMap for Room {
+        fn get_available_exits(&self, idx: usize) -> Vec<(usize, f32)> {
+            let here = self.index_to_point2d(idx);
+            [(-1, 0), (1, 0), (0, -1), (0, 1)]
+                .iter()
+                .map(|(dx, dy)| Point::new(here.x + dx, here.y + dy))
+                .filter(|p| self.in_bounds(*p))
+                .map(|p| (self.point2d_to_index(p), 1.0))
+                .collect()
+        }
+    }
+
+    const ROOM: Room = Room {
+        width: 10,
+        height: 10,
+    };
+
+    fn steps_between(a: usize, b: usize) -> i32 {
+        let (a, b) = (ROOM.index_to_point2d(a), ROOM.index_to_point2d(b));
+        (a.x - b.x).abs() + (a.y - b.y).abs()
+    }
+
+    #[test]
+    fn following_the_field_closes_in_on_the_goal() {
+        let goal = ROOM.point2d_to_index(Point::new(2, 3));
+        let field = FlowField::toward(100, &[goal], &ROOM);
+        let mut idx = ROOM.point2d_to_index(Point::new(9, 9));
+        while idx != goal {
+            let next = field
+                .next_step(idx, &ROOM)
+                .expect("Stuck short of the goal");
+            assert_eq!(steps_between(next, goal), steps_between(idx, goal) - 1);
+            idx = next;
+        }
+        assert_eq!(field.next_step(goal, &ROOM), None);
+    }
+
+    #[test]
+    fn following_the_away_field_flees_the_goal() {
+        let goal = ROOM.point2d_to_index(Point::new(2, 3));
+        let toward = FlowField::toward(100, &[goal], &ROOM);
+        let away = FlowField::away(&toward, &ROOM);
+        let mut idx = ROOM.point2d_to_index(Point::new(3, 3));
+        while let Some(next) = away.next_step(idx, &ROOM) {
+            assert!(steps_between(next, goal) > steps_between(idx, goal));
+            idx = next;
+        }
+        // Ends up in the corner furthest from the goal
+        assert_eq!(idx, ROOM.point2d_to_index(Point::new(9, 9)));
+    }
+}
@@ -1,3 +1,69 @@
+use rltk::RGB;
+use serde::{Deserialize, Serialize};
+
+/// The oldest entries are dropped once the log grows past this
+pub const MAX_LOG_ENTRIES: usize = 1000;
+
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug)]
+pub enum LogCategory {
+  Combat,
+  Loot,
+  System,
+}
+
+impl LogCategory {
+  pub fn color(self) -> RGB {
+    match self {
+      LogCategory::Combat => RGB::named(rltk::ORANGE),
+      LogCategory::Loot => RGB::named(rltk::GOLD),
+      LogCategory::System => RGB::named(rltk::WHITE),
+    }
+  }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LogEntry {
+  pub text: String,
+  pub category: LogCategory,
+  /// The player turn the entry was written on
+  pub turn: i32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub struct GameLog {
-  pub entries: Vec<String>,
+  pub entries: Vec<LogEntry>,
+  /// How many turns the player has taken this run
+  pub turn: i32,
+}
+
+impl GameLog {
+  pub fn new(welcome: String) -> GameLog {
+    let mut log = GameLog::default();
+    log.system(welcome);
+    log
+  }
+
+  pub fn combat(&mut self, text: String) {
+    self.add(LogCategory::Combat, text);
+  }
+
+  pub fn loot(&mut self, text: String) {
+    self.add(LogCategory::Loot, text);
+  }
+
+  pub fn system(&mut self, text: String) {
+    self.add(LogCategory::System, text);
+  }
+
+  fn add(&mut self, category: LogCategory, text: String) {
+    self.entries.push(LogEntry {
+      text,
+      category,
+      turn: self.turn,
+    });
+    if self.entries.len() > MAX_LOG_ENTRIES {
+      let excess = self.entries.len() - MAX_LOG_ENTRIES;
+      self.entries.drain(0..excess);
+    }
+  }
 }
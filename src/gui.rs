@@ -1,7 +1,15 @@
 use super::{
-  gamelog::GameLog, map::Map, CombatStats, Equipped, InBackpack, Name, Player, Point, Position,
-  RunState, State, Viewshed,
+  gamelog::{GameLog, LogCategory, LogEntry},
+  identification::IdentificationTable,
+  map::{Map, TileType},
+  morgue::DeathRecap,
+  saveload_system::{GameMode, SaveSlot},
+  Ammunition, AreaOfEffect, CombatStats, DefenseBonus, Equipped, Experience, Hidden, HungerClock,
+  HungerState, InBackpack, InflictsDamage, Item, MeleeWeapon, Name, Player, Point, Position,
+  ProvidesHealing, Ranged, RangedWeapon, RunState, State, StatusEffectType, StatusEffects,
+  Viewshed,
 };
+use chrono::TimeZone;
 use rltk::{Console, Rltk, VirtualKeyCode, RGB};
 use specs::prelude::*;
 use std::iter::FromIterator;
@@ -31,6 +39,24 @@ fn draw_menu_box(ctx: &mut Rltk, item_count: usize, offset: i32, menu_text: &str
   );
 }
 
+/// Highlights the menu row under the mouse, and returns it if it was
+/// clicked
+fn clicked_menu_row(ctx: &mut Rltk, first_row: i32, item_count: usize) -> Option<usize> {
+  let (mouse_x, mouse_y) = ctx.mouse_pos();
+  let row = mouse_y - first_row;
+  if !(16..=45).contains(&mouse_x) || row < 0 || row >= item_count as i32 {
+    return None;
+  }
+  for x in 16..46 {
+    ctx.set_bg(x, mouse_y, RGB::named(rltk::DIM_GREY));
+  }
+  if ctx.left_click {
+    Some(row as usize)
+  } else {
+    None
+  }
+}
+
 pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
   ctx.draw_box(
     0,
@@ -68,7 +94,7 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
     ctx.draw_bar_horizontal(
       28,
       43,
-      51,
+      20,
       stats.hp,
       stats.max_hp,
       RGB::named(rltk::RED),
@@ -76,12 +102,87 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
     );
   }
 
+  // Display level and progress towards the next one
+  let experience = ecs.read_storage::<Experience>();
+  for (_player, xp) in (&players, &experience).join() {
+    let level = format!("Lvl: {}", xp.level);
+    ctx.print_color(
+      50,
+      43,
+      RGB::named(rltk::YELLOW),
+      RGB::named(rltk::BLACK),
+      &level,
+    );
+
+    ctx.draw_bar_horizontal(
+      57,
+      43,
+      21,
+      xp.xp,
+      xp.xp_to_next_level(),
+      RGB::named(rltk::GOLD),
+      RGB::named(rltk::BLACK),
+    );
+  }
+
+  // Display hunger, saying nothing while it is normal
+  let hunger = ecs.read_storage::<HungerClock>();
+  for (_player, hunger) in (&players, &hunger).join() {
+    match hunger.state {
+      HungerState::WellFed => ctx.print_color(
+        71,
+        42,
+        RGB::named(rltk::GREEN),
+        RGB::named(rltk::BLACK),
+        "Well Fed",
+      ),
+      HungerState::Normal => {}
+      HungerState::Hungry => ctx.print_color(
+        71,
+        42,
+        RGB::named(rltk::ORANGE),
+        RGB::named(rltk::BLACK),
+        "Hungry",
+      ),
+      HungerState::Starving => ctx.print_color(
+        71,
+        42,
+        RGB::named(rltk::RED),
+        RGB::named(rltk::BLACK),
+        "Starving",
+      ),
+    }
+  }
+
+  // Display the player's status effects, right to left from the hunger display
+  let status_effects = ecs.read_storage::<StatusEffects>();
+  let mut x = 70;
+  for (_player, status) in (&players, &status_effects).join() {
+    for effect in status.effects.iter() {
+      let text = format!("{}({})", effect.kind.name(), effect.turns);
+      x -= text.len() as i32 + 1;
+      ctx.print_color(
+        x,
+        42,
+        status_color(effect.kind),
+        RGB::named(rltk::BLACK),
+        &text,
+      );
+    }
+  }
+
   // Display game log
   let log = ecs.fetch::<GameLog>();
   let mut y = 44;
-  for s in log.entries.iter().rev() {
+  for entry in log.entries.iter().rev() {
     if y < 49 {
-      ctx.print(2, y, s);
+      ctx.print_color(
+        2,
+        y,
+        entry.category.color(),
+        RGB::named(rltk::BLACK),
+        &entry.text,
+      );
     }
     y += 1;
   }
@@ -92,101 +193,199 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
   draw_tooltips(ecs, ctx);
 }
 
+fn status_color(kind: StatusEffectType) -> RGB {
+  match kind {
+    StatusEffectType::Poison => RGB::named(rltk::GREEN),
+    StatusEffectType::Regeneration => RGB::named(rltk::PINK),
+    StatusEffectType::Haste => RGB::named(rltk::CYAN),
+    StatusEffectType::Slow => RGB::named(rltk::BROWN1),
+    StatusEffectType::Blindness => RGB::named(rltk::GREY),
+    StatusEffectType::Paralysis => RGB::named(rltk::YELLOW),
+    StatusEffectType::Confusion => RGB::named(rltk::MAGENTA),
+    StatusEffectType::Clairvoyance => RGB::named(rltk::LIGHT_BLUE),
+  }
+}
+
+/// One line of a tooltip. Names are headers, the details under them are
+/// indented.
+struct TooltipLine {
+  text: String,
+  fg: RGB,
+}
+
+impl TooltipLine {
+  fn header(text: String) -> TooltipLine {
+    TooltipLine {
+      text,
+      fg: RGB::named(rltk::WHITE),
+    }
+  }
+
+  fn detail(text: String, fg: RGB) -> TooltipLine {
+    TooltipLine {
+      text: format!(" {}", text),
+      fg,
+    }
+  }
+}
+
+/// What an item does, as far as the player knows it
+fn item_stats(ecs: &World, item: Entity) -> Vec<String> {
+  let mut stats = Vec::new();
+  if let Some(weapon) = ecs.read_storage::<MeleeWeapon>().get(item) {
+    let dice = format!("{}d{}", weapon.n_dice, weapon.die_type);
+    match weapon.bonus {
+      0 => stats.push(format!("Melee {}", dice)),
+      bonus => stats.push(format!("Melee {}{:+}", dice, bonus)),
+    }
+  }
+  if let Some(weapon) = ecs.read_storage::<RangedWeapon>().get(item) {
+    let dice = format!("{}d{}", weapon.n_dice, weapon.die_type);
+    match weapon.bonus {
+      0 => stats.push(format!("Shoots {}, range {}", dice, weapon.range)),
+      bonus => stats.push(format!(
+        "Shoots {}{:+}, range {}",
+        dice, bonus, weapon.range
+      )),
+    }
+  }
+  if let Some(bonus) = ecs.read_storage::<DefenseBonus>().get(item) {
+    stats.push(format!("Defense {:+}", bonus.defense));
+  }
+  if let Some(ammo) = ecs.read_storage::<Ammunition>().get(item) {
+    stats.push(format!("{} left", ammo.count));
+  }
+  if let Some(healing) = ecs.read_storage::<ProvidesHealing>().get(item) {
+    stats.push(format!("Heals {} hp", healing.heal_amount));
+  }
+  if let Some(damage) = ecs.read_storage::<InflictsDamage>().get(item) {
+    stats.push(format!("Deals {} dmg", damage.damage));
+  }
+  if let Some(aoe) = ecs.read_storage::<AreaOfEffect>().get(item) {
+    stats.push(format!("Radius {}", aoe.radius));
+  }
+  if let Some(ranged) = ecs.read_storage::<Ranged>().get(item) {
+    stats.push(format!("Range {}", ranged.range));
+  }
+  stats
+}
+
+fn tile_name(tile: TileType) -> &'static str {
+  match tile {
+    TileType::Wall => "Wall",
+    TileType::Floor => "Floor",
+    TileType::DownStairs => "Stairs down",
+    TileType::UpStairs => "Stairs up",
+  }
+}
+
 fn draw_tooltips(ecs: &World, ctx: &mut Rltk) {
   let map = ecs.fetch::<Map>();
   let names = ecs.read_storage::<Name>();
   let positions = ecs.read_storage::<Position>();
+  let status_effects = ecs.read_storage::<StatusEffects>();
+  let combat_stats = ecs.read_storage::<CombatStats>();
+  let items = ecs.read_storage::<Item>();
+  let hidden = ecs.read_storage::<Hidden>();
+  let identification = ecs.fetch::<IdentificationTable>();
+  let entities = ecs.entities();
 
   let mouse_pos = ctx.mouse_pos();
-  if mouse_pos.0 >= map.width || mouse_pos.1 >= map.height {
+  if mouse_pos.0 >= map.width || mouse_pos.1 >= map.height || mouse_pos.0 < 0 || mouse_pos.1 < 0 {
     return;
   }
+  let idx = map.xy_idx(mouse_pos.0, mouse_pos.1);
 
-  let mut tooltip: Vec<String> = Vec::new();
-  for (name, position) in (&names, &positions).join() {
-    let idx = map.xy_idx(position.x, position.y);
-    if position.x == mouse_pos.0 && position.y == mouse_pos.1 && map.visible_tiles[idx] {
-      tooltip.push(name.name.to_string());
-    }
-  }
+  let mut tooltip: Vec<TooltipLine> = Vec::new();
+  if map.visible_tiles[idx] {
+    for (entity, name, position, _hidden) in (&entities, &names, &positions, !&hidden).join() {
+      if position.x != mouse_pos.0 || position.y != mouse_pos.1 {
+        continue;
+      }
+      let known_as = identification.display_name(&name.name);
+      let identified = known_as == name.name;
+      tooltip.push(TooltipLine::header(known_as));
 
-  if !tooltip.is_empty() {
-    let mut width: i32 = 0;
-    for s in tooltip.iter() {
-      if width < s.len() as i32 {
-        width = s.len() as i32;
+      if let Some(stats) = combat_stats.get(entity) {
+        tooltip.push(TooltipLine::detail(
+          format!("HP: {}/{}", stats.hp, stats.max_hp),
+          RGB::named(rltk::RED),
+        ));
       }
-    }
-    width += 3;
-
-    if mouse_pos.0 > 40 {
-      let arrow_pos = Point::new(mouse_pos.0 - 2, mouse_pos.1);
-      let left_x = mouse_pos.0 - width;
-      let mut y = mouse_pos.1;
-
-      for s in tooltip.iter() {
-        ctx.print_color(
-          left_x,
-          y,
-          RGB::named(rltk::WHITE),
-          RGB::named(rltk::GREY),
-          s,
-        );
-        let padding = (width - s.len() as i32) - 1;
-        for i in 0..padding {
-          ctx.print_color(
-            arrow_pos.x - i,
-            y,
-            RGB::named(rltk::WHITE),
-            RGB::named(rltk::GREY),
-            &" ".to_string(),
-          );
+      if let Some(status) = status_effects.get(entity) {
+        for effect in status.effects.iter() {
+          tooltip.push(TooltipLine::detail(
+            format!("{}({})", effect.kind.name(), effect.turns),
+            status_color(effect.kind),
+          ));
         }
-        y += 1;
       }
-
-      ctx.print_color(
-        arrow_pos.x,
-        arrow_pos.y,
-        RGB::named(rltk::WHITE),
-        RGB::named(rltk::GREY),
-        &"->".to_string(),
-      );
-    } else {
-      let arrow_pos = Point::new(mouse_pos.0 + 1, mouse_pos.1);
-      let left_x = mouse_pos.0 + 3;
-      let mut y = mouse_pos.1;
-
-      for s in tooltip.iter() {
-        ctx.print_color(
-          left_x + 1,
-          y,
-          RGB::named(rltk::WHITE),
-          RGB::named(rltk::GREY),
-          s,
-        );
-        let padding = (width - s.len() as i32) - 1;
-        for i in 0..padding {
-          ctx.print_color(
-            arrow_pos.x + 1 + i,
-            y,
-            RGB::named(rltk::WHITE),
-            RGB::named(rltk::GREY),
-            &" ".to_string(),
-          );
+      // Unidentified items keep their secrets
+      if items.get(entity).is_some() && identified {
+        for stat in item_stats(ecs, entity) {
+          tooltip.push(TooltipLine::detail(stat, RGB::named(rltk::CYAN)));
         }
-        y += 1;
       }
-
-      ctx.print_color(
-        arrow_pos.x,
-        arrow_pos.y,
-        RGB::named(rltk::WHITE),
-        RGB::named(rltk::GREY),
-        &"<-".to_string(),
-      );
     }
+  } else if map.revealed_tiles[idx] {
+    tooltip.push(TooltipLine::header(tile_name(map.tiles[idx]).to_string()));
+    tooltip.push(TooltipLine::detail(
+      "remembered".to_string(),
+      RGB::named(rltk::GREY),
+    ));
+  }
+
+  if tooltip.is_empty() {
+    return;
+  }
+
+  let width = tooltip
+    .iter()
+    .map(|line| line.text.len() as i32)
+    .max()
+    .unwrap_or(0)
+    + 1;
+  let height = tooltip.len() as i32 + 1;
+
+  // Sit to the right of the cursor, unless that would run off the screen
+  let flip = mouse_pos.0 + 3 + width > 79;
+  let box_x = if flip {
+    mouse_pos.0 - 3 - width
+  } else {
+    mouse_pos.0 + 3
+  };
+  let box_y = i32::min(i32::max(mouse_pos.1 - 1, 0), 49 - height);
+
+  ctx.draw_box(
+    box_x,
+    box_y,
+    width,
+    height,
+    RGB::named(rltk::WHITE),
+    RGB::named(rltk::DIM_GREY),
+  );
+  for (i, line) in tooltip.iter().enumerate() {
+    ctx.print_color(
+      box_x + 1,
+      box_y + 1 + i as i32,
+      line.fg,
+      RGB::named(rltk::DIM_GREY),
+      &line.text,
+    );
   }
+
+  let arrow = if flip {
+    Point::new(mouse_pos.0 - 2, mouse_pos.1)
+  } else {
+    Point::new(mouse_pos.0 + 1, mouse_pos.1)
+  };
+  ctx.print_color(
+    arrow.x,
+    arrow.y,
+    RGB::named(rltk::WHITE),
+    RGB::named(rltk::DIM_GREY),
+    if flip { "->" } else { "<-" },
+  );
 }
 
 #[derive(PartialEq, Copy, Clone)]
@@ -196,10 +395,25 @@ pub enum ItemMenuResult {
   Selected,
 }
 
+/// How an item is listed in the backpack menus
+pub fn item_label(
+  name: &Name,
+  ammunition: Option<&Ammunition>,
+  identification: &IdentificationTable,
+) -> String {
+  let known_as = identification.display_name(&name.name);
+  match ammunition {
+    Some(ammo) => format!("{} ({})", known_as, ammo.count),
+    None => known_as,
+  }
+}
+
 pub fn show_inventory(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option<Entity>) {
   let player_entity = gs.ecs.fetch::<Entity>();
   let names = gs.ecs.read_storage::<Name>();
   let backpack = gs.ecs.read_storage::<InBackpack>();
+  let ammunition = gs.ecs.read_storage::<Ammunition>();
+  let identification = gs.ecs.fetch::<IdentificationTable>();
   let entities = gs.ecs.entities();
 
   let inventory = (&backpack, &names)
@@ -207,6 +421,7 @@ pub fn show_inventory(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option
     .filter(|item| item.0.owner == *player_entity);
   let count = inventory.count();
   let mut y = (25 - (count / 2)) as i32;
+  let first_row = y;
   draw_menu_box(ctx, count, y, "Inventory");
 
   let mut equippable: Vec<Entity> = Vec::new();
@@ -237,12 +452,20 @@ pub fn show_inventory(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option
       rltk::to_cp437(')'),
     );
 
-    ctx.print(21, y, &name.name.to_string());
+    ctx.print(
+      21,
+      y,
+      &item_label(name, ammunition.get(entity), &identification),
+    );
     equippable.push(entity);
     y += 1;
     j += 1;
   }
 
+  if let Some(row) = clicked_menu_row(ctx, first_row, count) {
+    return (ItemMenuResult::Selected, Some(equippable[row]));
+  }
+
   match ctx.key {
     None => (ItemMenuResult::NoResponse, None),
     Some(key) => match key {
@@ -265,6 +488,8 @@ pub fn drop_item_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option
   let player_entity = gs.ecs.fetch::<Entity>();
   let names = gs.ecs.read_storage::<Name>();
   let backpack = gs.ecs.read_storage::<InBackpack>();
+  let ammunition = gs.ecs.read_storage::<Ammunition>();
+  let identification = gs.ecs.fetch::<IdentificationTable>();
   let entities = gs.ecs.entities();
 
   let inventory = (&backpack, &names)
@@ -273,6 +498,7 @@ pub fn drop_item_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option
 
   let count = inventory.count();
   let mut y = (25 - (count / 2)) as i32;
+  let first_row = y;
   draw_menu_box(ctx, count, y, "Drop Which Item?");
 
   let mut equippable: Vec<Entity> = Vec::new();
@@ -303,12 +529,20 @@ pub fn drop_item_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option
       rltk::to_cp437(')'),
     );
 
-    ctx.print(21, y, &name.name.to_string());
+    ctx.print(
+      21,
+      y,
+      &item_label(name, ammunition.get(entity), &identification),
+    );
     equippable.push(entity);
     y += 1;
     j += 1;
   }
 
+  if let Some(row) = clicked_menu_row(ctx, first_row, count) {
+    return (ItemMenuResult::Selected, Some(equippable[row]));
+  }
+
   match ctx.key {
     None => (ItemMenuResult::NoResponse, None),
     Some(key) => match key {
@@ -369,6 +603,9 @@ pub fn ranged_target(
       valid_target = true;
     }
   }
+  if ctx.key == Some(VirtualKeyCode::Escape) {
+    return (ItemMenuResult::Cancel, None);
+  }
   if valid_target {
     ctx.set_bg(mouse_pos.0, mouse_pos.1, RGB::named(rltk::CYAN));
     if ctx.left_click {
@@ -390,6 +627,7 @@ pub fn ranged_target(
 #[derive(PartialEq, Copy, Clone)]
 pub enum MainMenuSelection {
   NewGame,
+  SeededGame,
   LoadGame,
   Quit,
 }
@@ -428,6 +666,11 @@ pub fn main_menu(gs: &mut State, ctx: &mut Rltk) -> MainMenuResult {
         text: "Begin New Game",
         visible: true,
       },
+      MenuItem {
+        selection: MainMenuSelection::SeededGame,
+        text: "Begin Game From Seed",
+        visible: true,
+      },
       MenuItem {
         selection: MainMenuSelection::LoadGame,
         text: "Load Game",
@@ -456,6 +699,17 @@ pub fn main_menu(gs: &mut State, ctx: &mut Rltk) -> MainMenuResult {
       );
     }
 
+    if let Some(error) = &gs.menu_error {
+      for (i, line) in wrap_words(error, 60).iter().enumerate() {
+        ctx.print_color_centered(
+          26 + options.len() as i32 + i as i32,
+          RGB::named(rltk::RED),
+          RGB::named(rltk::BLACK),
+          line,
+        );
+      }
+    }
+
     match ctx.key {
       None => {
         return MainMenuResult::NoSelection {
@@ -529,10 +783,327 @@ pub fn main_menu(gs: &mut State, ctx: &mut Rltk) -> MainMenuResult {
   }
 }
 
+pub enum ModeMenuResult {
+  Choosing { mode: GameMode },
+  Chosen { mode: GameMode },
+  Cancel,
+}
+
+/// Lets the player pick how a new run treats its saves
+pub fn choose_mode(ctx: &mut Rltk, mode: GameMode) -> ModeMenuResult {
+  ctx.print_color_centered(
+    15,
+    RGB::named(rltk::YELLOW),
+    RGB::named(rltk::BLACK),
+    "Choose how to play",
+  );
+
+  let options = [
+    (
+      GameMode::Casual,
+      "Casual",
+      "Saves are checkpoints you can return to after dying",
+    ),
+    (
+      GameMode::Permadeath,
+      "Permadeath",
+      "Loading a save deletes it, and death is final",
+    ),
+  ];
+  for (i, (option, name, description)) in options.iter().enumerate() {
+    let y = 20 + i as i32 * 3;
+    let fg = if *option == mode {
+      RGB::named(rltk::MAGENTA)
+    } else {
+      RGB::named(rltk::WHITE)
+    };
+    ctx.print_color_centered(y, fg, RGB::named(rltk::BLACK), name);
+    ctx.print_color_centered(
+      y + 1,
+      RGB::named(rltk::GREY),
+      RGB::named(rltk::BLACK),
+      description,
+    );
+  }
+  ctx.print_color_centered(
+    27,
+    RGB::named(rltk::WHITE),
+    RGB::named(rltk::BLACK),
+    "ENTER to begin, ESCAPE to cancel",
+  );
+
+  match ctx.key {
+    None => ModeMenuResult::Choosing { mode },
+    Some(key) => match key {
+      VirtualKeyCode::Escape => ModeMenuResult::Cancel,
+      VirtualKeyCode::Return => ModeMenuResult::Chosen { mode },
+      VirtualKeyCode::Up | VirtualKeyCode::Down => ModeMenuResult::Choosing {
+        mode: match mode {
+          GameMode::Casual => GameMode::Permadeath,
+          GameMode::Permadeath => GameMode::Casual,
+        },
+      },
+      _ => ModeMenuResult::Choosing { mode },
+    },
+  }
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum SlotMenuMode {
+  Load,
+  Save,
+}
+
+pub enum SlotMenuResult {
+  Selecting { selection: usize, confirming: bool },
+  Chosen { slot: usize },
+  Delete { slot: usize },
+  Cancel,
+}
+
+/// The two lines describing a slot, and the colour they're drawn in
+fn describe_slot(slot: &SaveSlot) -> (String, String, RGB) {
+  match slot {
+    SaveSlot::Empty => ("Empty".to_string(), String::new(), RGB::named(rltk::GREY)),
+    SaveSlot::Saved(metadata) => {
+      let saved_at = chrono::Local
+        .timestamp_opt(metadata.timestamp, 0)
+        .single()
+        .map_or("at an unknown time".to_string(), |time| {
+          time.format("%Y-%m-%d %H:%M").to_string()
+        });
+      let seed = metadata
+        .seed
+        .map_or(String::new(), |seed| format!(", seed {}", seed));
+      let mode = match metadata.mode {
+        GameMode::Casual => "",
+        GameMode::Permadeath => " (permadeath)",
+      };
+      (
+        format!(
+          "Level {} on depth {}, turn {}{}",
+          metadata.level, metadata.depth, metadata.turn, mode
+        ),
+        format!("Saved {}{}", saved_at, seed),
+        RGB::named(rltk::WHITE),
+      )
+    }
+    SaveSlot::Unreadable(error) => (
+      "Unreadable save".to_string(),
+      error.chars().take(60).collect(),
+      RGB::named(rltk::RED),
+    ),
+  }
+}
+
+/// Lists the save slots to load from, or to save the current run into.
+/// Saves can be deleted from the load screen. Deleting a save, or saving
+/// over one from another run, waits for the player to confirm it.
+pub fn save_slot_menu(
+  ctx: &mut Rltk,
+  mode: SlotMenuMode,
+  selection: usize,
+  confirming: bool,
+  slots: &[SaveSlot],
+  own_slot: Option<usize>,
+) -> SlotMenuResult {
+  let title = match mode {
+    SlotMenuMode::Load => "Load Game",
+    SlotMenuMode::Save => "Save Game",
+  };
+  ctx.print_color_centered(12, RGB::named(rltk::YELLOW), RGB::named(rltk::BLACK), title);
+
+  for (i, slot) in slots.iter().enumerate() {
+    let y = 16 + i as i32 * 3;
+    let (summary, detail, color) = describe_slot(slot);
+    let fg = if i == selection {
+      RGB::named(rltk::MAGENTA)
+    } else {
+      color
+    };
+    ctx.print_color(
+      15,
+      y,
+      fg,
+      RGB::named(rltk::BLACK),
+      &format!("{}. {}", i + 1, summary),
+    );
+    ctx.print_color(
+      18,
+      y + 1,
+      RGB::named(rltk::GREY),
+      RGB::named(rltk::BLACK),
+      &detail,
+    );
+  }
+
+  let footer = if confirming {
+    match mode {
+      SlotMenuMode::Load => format!(
+        "Delete save {}? Y to confirm, any other key to keep it",
+        selection + 1
+      ),
+      SlotMenuMode::Save => format!(
+        "Replace save {}? Y to confirm, any other key to keep it",
+        selection + 1
+      ),
+    }
+  } else {
+    match mode {
+      SlotMenuMode::Load => "ENTER to load, D to delete, ESCAPE to go back".to_string(),
+      SlotMenuMode::Save => "ENTER to save, ESCAPE to return to the game".to_string(),
+    }
+  };
+  ctx.print_color_centered(
+    18 + slots.len() as i32 * 3,
+    if confirming {
+      RGB::named(rltk::RED)
+    } else {
+      RGB::named(rltk::WHITE)
+    },
+    RGB::named(rltk::BLACK),
+    &footer,
+  );
+
+  let selected_empty = matches!(slots.get(selection), None | Some(SaveSlot::Empty));
+  // The run's own save is replaced without asking
+  let overwrites = !selected_empty && own_slot != Some(selection);
+  match ctx.key {
+    None => SlotMenuResult::Selecting {
+      selection,
+      confirming,
+    },
+    Some(key) if confirming => match key {
+      VirtualKeyCode::Y if mode == SlotMenuMode::Load => SlotMenuResult::Delete { slot: selection },
+      VirtualKeyCode::Y => SlotMenuResult::Chosen { slot: selection },
+      _ => SlotMenuResult::Selecting {
+        selection,
+        confirming: false,
+      },
+    },
+    Some(key) => match key {
+      VirtualKeyCode::Escape => SlotMenuResult::Cancel,
+      VirtualKeyCode::Up => SlotMenuResult::Selecting {
+        selection: (selection + slots.len() - 1) % slots.len(),
+        confirming: false,
+      },
+      VirtualKeyCode::Down => SlotMenuResult::Selecting {
+        selection: (selection + 1) % slots.len(),
+        confirming: false,
+      },
+      VirtualKeyCode::D if mode == SlotMenuMode::Load && !selected_empty => {
+        SlotMenuResult::Selecting {
+          selection,
+          confirming: true,
+        }
+      }
+      VirtualKeyCode::Return if mode == SlotMenuMode::Save && overwrites => {
+        SlotMenuResult::Selecting {
+          selection,
+          confirming: true,
+        }
+      }
+      VirtualKeyCode::Return if mode == SlotMenuMode::Save || !selected_empty => {
+        SlotMenuResult::Chosen { slot: selection }
+      }
+      _ => SlotMenuResult::Selecting {
+        selection,
+        confirming: false,
+      },
+    },
+  }
+}
+
+fn list_or_nothing(items: &[String]) -> String {
+  if items.is_empty() {
+    "nothing".to_string()
+  } else {
+    items.join(", ")
+  }
+}
+
+/// Breaks text into lines no longer than the width, between words
+fn wrap_words(text: &str, width: usize) -> Vec<String> {
+  let mut lines: Vec<String> = Vec::new();
+  let mut line = String::new();
+  for word in text.split_whitespace() {
+    if !line.is_empty() && line.len() + 1 + word.len() > width {
+      lines.push(line);
+      line = String::new();
+    }
+    if !line.is_empty() {
+      line.push(' ');
+    }
+    line.push_str(word);
+  }
+  if !line.is_empty() {
+    lines.push(line);
+  }
+  lines
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum SeedEntryResult {
+  Editing { seed: u64 },
+  Cancel,
+  Confirmed { seed: u64 },
+}
+
+pub fn seed_entry(ctx: &mut Rltk, seed: u64) -> SeedEntryResult {
+  ctx.print_color_centered(
+    15,
+    RGB::named(rltk::YELLOW),
+    RGB::named(rltk::BLACK),
+    "Enter a seed",
+  );
+  ctx.print_color_centered(
+    24,
+    RGB::named(rltk::MAGENTA),
+    RGB::named(rltk::BLACK),
+    &format!("{}_", seed),
+  );
+  ctx.print_color_centered(
+    26,
+    RGB::named(rltk::WHITE),
+    RGB::named(rltk::BLACK),
+    "ENTER to begin, ESCAPE to cancel",
+  );
+
+  match ctx.key {
+    None => SeedEntryResult::Editing { seed },
+    Some(key) => match key {
+      VirtualKeyCode::Escape => SeedEntryResult::Cancel,
+      VirtualKeyCode::Return => SeedEntryResult::Confirmed { seed },
+      VirtualKeyCode::Back => SeedEntryResult::Editing { seed: seed / 10 },
+      _ => {
+        let digit = match key {
+          VirtualKeyCode::Key0 | VirtualKeyCode::Numpad0 => Some(0),
+          VirtualKeyCode::Key1 | VirtualKeyCode::Numpad1 => Some(1),
+          VirtualKeyCode::Key2 | VirtualKeyCode::Numpad2 => Some(2),
+          VirtualKeyCode::Key3 | VirtualKeyCode::Numpad3 => Some(3),
+          VirtualKeyCode::Key4 | VirtualKeyCode::Numpad4 => Some(4),
+          VirtualKeyCode::Key5 | VirtualKeyCode::Numpad5 => Some(5),
+          VirtualKeyCode::Key6 | VirtualKeyCode::Numpad6 => Some(6),
+          VirtualKeyCode::Key7 | VirtualKeyCode::Numpad7 => Some(7),
+          VirtualKeyCode::Key8 | VirtualKeyCode::Numpad8 => Some(8),
+          VirtualKeyCode::Key9 | VirtualKeyCode::Numpad9 => Some(9),
+          _ => None,
+        };
+        // Ignore digits that would overflow the seed
+        let new_seed = digit.and_then(|digit| seed.checked_mul(10)?.checked_add(digit));
+        SeedEntryResult::Editing {
+          seed: new_seed.unwrap_or(seed),
+        }
+      }
+    },
+  }
+}
+
 pub fn unequip_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option<Entity>) {
   let player_entity = gs.ecs.fetch::<Entity>();
   let names = gs.ecs.read_storage::<Name>();
   let equipped_items = gs.ecs.read_storage::<Equipped>();
+  let identification = gs.ecs.fetch::<IdentificationTable>();
   let entities = gs.ecs.entities();
 
   let player_equipped = (&equipped_items, &names)
@@ -541,6 +1112,7 @@ pub fn unequip_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option<E
 
   let count = player_equipped.count();
   let mut y = (25 - (count / 2)) as i32;
+  let first_row = y;
   draw_menu_box(ctx, count, y, "Which item to Unequip?");
 
   let mut unequippable: Vec<Entity> = Vec::new();
@@ -571,12 +1143,16 @@ pub fn unequip_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option<E
       rltk::to_cp437(')'),
     );
 
-    ctx.print(21, y, &name.name.to_string());
+    ctx.print(21, y, &identification.display_name(&name.name));
     unequippable.push(entity);
     y += 1;
     j += 1;
   }
 
+  if let Some(row) = clicked_menu_row(ctx, first_row, count) {
+    return (ItemMenuResult::Selected, Some(unequippable[row]));
+  }
+
   match ctx.key {
     None => (ItemMenuResult::NoResponse, None),
     Some(key) => match key {
@@ -595,40 +1171,305 @@ pub fn unequip_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option<E
   }
 }
 
+#[derive(PartialEq, Copy, Clone)]
+pub enum LevelUpChoice {
+  Health,
+  Power,
+  Defense,
+}
+
+pub enum LevelUpResult {
+  NoSelection,
+  Selected { choice: LevelUpChoice },
+}
+
+pub fn level_up_menu(ctx: &mut Rltk) -> LevelUpResult {
+  let choices = [
+    (LevelUpChoice::Health, "+10 maximum health"),
+    (LevelUpChoice::Power, "+1 power"),
+    (LevelUpChoice::Defense, "+1 defense"),
+  ];
+
+  let top = (25 - (choices.len() / 2)) as i32;
+  ctx.draw_box(
+    15,
+    top - 2,
+    31,
+    (choices.len() + 3) as i32,
+    RGB::named(rltk::WHITE),
+    RGB::named(rltk::BLACK),
+  );
+  ctx.print_color(
+    18,
+    top - 2,
+    RGB::named(rltk::YELLOW),
+    RGB::named(rltk::BLACK),
+    "You feel stronger! Choose:",
+  );
+
+  for (j, (_choice, text)) in choices.iter().enumerate() {
+    let y = top + j as i32;
+    ctx.set(
+      17,
+      y,
+      RGB::named(rltk::WHITE),
+      RGB::named(rltk::BLACK),
+      rltk::to_cp437('('),
+    );
+    ctx.set(
+      18,
+      y,
+      RGB::named(rltk::YELLOW),
+      RGB::named(rltk::BLACK),
+      97 + j as u8,
+    );
+    ctx.set(
+      19,
+      y,
+      RGB::named(rltk::WHITE),
+      RGB::named(rltk::BLACK),
+      rltk::to_cp437(')'),
+    );
+    ctx.print(21, y, text);
+  }
+
+  match ctx.key {
+    None => LevelUpResult::NoSelection,
+    Some(key) => {
+      let selection = rltk::letter_to_option(key);
+      if selection > -1 && selection < choices.len() as i32 {
+        return LevelUpResult::Selected {
+          choice: choices[selection as usize].0,
+        };
+      }
+      LevelUpResult::NoSelection
+    }
+  }
+}
+
 pub enum GameOverResult {
   NoSelection,
   QuitToMenu,
+  LoadCheckpoint,
 }
 
-pub fn game_over(ctx: &mut Rltk) -> GameOverResult {
+/// Recaps the run, and offers to go back to the last checkpoint when a
+/// casual run has one
+pub fn game_over(ctx: &mut Rltk, checkpoint: bool, recap: Option<&DeathRecap>) -> GameOverResult {
   ctx.print_color_centered(
-    15,
+    3,
     RGB::named(rltk::YELLOW),
     RGB::named(rltk::BLACK),
     "Your journey has ended!",
   );
-  ctx.print_color_centered(
-    17,
+
+  let mut y = 5;
+  if let Some(recap) = recap {
+    ctx.print_color_centered(
+      y,
+      RGB::named(rltk::WHITE),
+      RGB::named(rltk::BLACK),
+      &recap.epitaph(),
+    );
+    y += 2;
+
+    let lists = [
+      ("Kills", recap.kill_summary()),
+      ("Equipped", list_or_nothing(&recap.equipment)),
+      ("Carrying", list_or_nothing(&recap.inventory)),
+    ];
+    for (title, list) in lists.iter() {
+      for (i, line) in wrap_words(&format!("{}: {}", title, list), 70)
+        .iter()
+        .enumerate()
+      {
+        let x = if i == 0 { 5 } else { 7 };
+        ctx.print_color(x, y, RGB::named(rltk::WHITE), RGB::named(rltk::BLACK), line);
+        y += 1;
+      }
+    }
+
+    y += 1;
+    ctx.print_color(
+      5,
+      y,
+      RGB::named(rltk::YELLOW),
+      RGB::named(rltk::BLACK),
+      "Last messages:",
+    );
+    y += 1;
+    for message in recap.last_messages.iter() {
+      let text: String = message.chars().take(70).collect();
+      ctx.print_color(7, y, RGB::named(rltk::GREY), RGB::named(rltk::BLACK), &text);
+      y += 1;
+    }
+
+    y += 1;
+    let morgue = match &recap.morgue_file {
+      Some(path) => format!("Morgue file written to {}", path.display()),
+      None => "The morgue file could not be written.".to_string(),
+    };
+    ctx.print_color_centered(y, RGB::named(rltk::GREY), RGB::named(rltk::BLACK), &morgue);
+    y += 2;
+  }
+
+  if checkpoint {
+    ctx.print_color_centered(
+      y,
+      RGB::named(rltk::MAGENTA),
+      RGB::named(rltk::BLACK),
+      "Press L to return to your last checkpoint,",
+    );
+    ctx.print_color_centered(
+      y + 1,
+      RGB::named(rltk::MAGENTA),
+      RGB::named(rltk::BLACK),
+      "or any other key to return to the menu.",
+    );
+  } else {
+    ctx.print_color_centered(
+      y,
+      RGB::named(rltk::MAGENTA),
+      RGB::named(rltk::BLACK),
+      "Press any key to return to the menu.",
+    );
+  }
+
+  match ctx.key {
+    None => GameOverResult::NoSelection,
+    Some(VirtualKeyCode::L) if checkpoint => GameOverResult::LoadCheckpoint,
+    Some(_) => GameOverResult::QuitToMenu,
+  }
+}
+
+/// Which categories of entries the log viewer shows
+#[derive(PartialEq, Copy, Clone)]
+pub struct LogFilter {
+  pub combat: bool,
+  pub loot: bool,
+  pub system: bool,
+}
+
+impl Default for LogFilter {
+  fn default() -> LogFilter {
+    LogFilter {
+      combat: true,
+      loot: true,
+      system: true,
+    }
+  }
+}
+
+impl LogFilter {
+  fn shows(&self, category: LogCategory) -> bool {
+    match category {
+      LogCategory::Combat => self.combat,
+      LogCategory::Loot => self.loot,
+      LogCategory::System => self.system,
+    }
+  }
+}
+
+pub enum LogViewerResult {
+  Viewing { scroll: usize, filter: LogFilter },
+  Close,
+}
+
+/// Number of entries that fit on one page of the log viewer
+const LOG_PAGE_LINES: usize = 42;
+
+/// Full screen history of the game log. Scroll counts how many entries the
+/// view has moved up from the newest one.
+pub fn show_log(ecs: &World, ctx: &mut Rltk, scroll: usize, filter: LogFilter) -> LogViewerResult {
+  let log = ecs.fetch::<GameLog>();
+  let entries: Vec<&LogEntry> = log
+    .entries
+    .iter()
+    .filter(|entry| filter.shows(entry.category))
+    .collect();
+  let max_scroll = entries.len().saturating_sub(LOG_PAGE_LINES);
+  let scroll = usize::min(scroll, max_scroll);
+
+  ctx.cls();
+  ctx.draw_box(
+    0,
+    0,
+    79,
+    49,
     RGB::named(rltk::WHITE),
     RGB::named(rltk::BLACK),
-    "One day, we'll tell you all about how you did.",
   );
-  ctx.print_color_centered(
-    18,
-    RGB::named(rltk::WHITE),
+  ctx.print_color(
+    3,
+    0,
+    RGB::named(rltk::YELLOW),
     RGB::named(rltk::BLACK),
-    "That day, sadly, is not in this chapter..",
+    "Message Log",
   );
 
+  // Category toggles
+  let toggles = [
+    ("1", "Combat", LogCategory::Combat),
+    ("2", "Loot", LogCategory::Loot),
+    ("3", "System", LogCategory::System),
+  ];
+  let mut x = 2;
+  for (key, label, category) in toggles.iter() {
+    let fg = if filter.shows(*category) {
+      category.color()
+    } else {
+      RGB::named(rltk::DARK_GREY)
+    };
+    let text = format!("({}) {}", key, label);
+    ctx.print_color(x, 2, fg, RGB::named(rltk::BLACK), &text);
+    x += text.len() as i32 + 3;
+  }
+
+  let end = entries.len() - scroll;
+  let start = end.saturating_sub(LOG_PAGE_LINES);
+  for (i, entry) in entries[start..end].iter().enumerate() {
+    let y = 4 + i as i32;
+    ctx.print_color(
+      2,
+      y,
+      RGB::named(rltk::GREY),
+      RGB::named(rltk::BLACK),
+      &format!("{:>5}", entry.turn),
+    );
+    let text: String = entry.text.chars().take(70).collect();
+    ctx.print_color(8, y, entry.category.color(), RGB::named(rltk::BLACK), &text);
+  }
+
   ctx.print_color_centered(
-    20,
-    RGB::named(rltk::MAGENTA),
+    49,
+    RGB::named(rltk::WHITE),
     RGB::named(rltk::BLACK),
-    "Press any key to return to the menu.",
+    "PgUp/PgDn to scroll, 1-3 to filter, ESCAPE to close",
   );
 
-  match ctx.key {
-    None => GameOverResult::NoSelection,
-    Some(_) => GameOverResult::QuitToMenu,
-  }
+  let mut filter = filter;
+  let scroll = match ctx.key {
+    None => scroll,
+    Some(key) => match key {
+      VirtualKeyCode::Escape => return LogViewerResult::Close,
+      VirtualKeyCode::PageUp => usize::min(scroll + LOG_PAGE_LINES, max_scroll),
+      VirtualKeyCode::PageDown => scroll.saturating_sub(LOG_PAGE_LINES),
+      VirtualKeyCode::Up => usize::min(scroll + 1, max_scroll),
+      VirtualKeyCode::Down => scroll.saturating_sub(1),
+      VirtualKeyCode::Key1 => {
+        filter.combat = !filter.combat;
+        0
+      }
+      VirtualKeyCode::Key2 => {
+        filter.loot = !filter.loot;
+        0
+      }
+      VirtualKeyCode::Key3 => {
+        filter.system = !filter.system;
+        0
+      }
+      _ => scroll,
+    },
+  };
+  LogViewerResult::Viewing { scroll, filter }
 }
@@ -0,0 +1,100 @@
+use super::raws::{RawMaster, UnidentifiedKind};
+use rltk::RandomNumberGenerator;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+const SCROLL_SYLLABLES: &[&str] = &[
+    "ab", "ra", "ca", "da", "xy", "zzy", "fro", "bo", "zim", "kel", "mor", "nu", "thu", "gar",
+    "elb", "ish", "vo", "pel", "qua", "rin",
+];
+
+const POTION_ADJECTIVES: &[&str] = &[
+    "murky",
+    "bubbling",
+    "cloudy",
+    "fizzy",
+    "golden",
+    "smoky",
+    "oily",
+    "glowing",
+    "milky",
+    "viscous",
+    "swirling",
+    "icy",
+    "tepid",
+    "sparkling",
+    "inky",
+    "pungent",
+];
+
+/// The made up names scrolls and potions go by in one run, and which of
+/// them the player has worked out. Part of the save so the names don't
+/// change on reload.
+#[derive(Default, Serialize, Deserialize, Clone)]
+pub struct IdentificationTable {
+    unidentified_names: HashMap<String, String>,
+    identified: HashSet<String>,
+}
+
+impl IdentificationTable {
+    /// Gives every unidentified item in the raws a name of its own
+    pub fn new(raws: &RawMaster, rng: &mut RandomNumberGenerator) -> IdentificationTable {
+        let mut used: HashSet<String> = HashSet::new();
+        let mut unidentified_names = HashMap::new();
+
+        for (name, kind) in raws.unidentified_items() {
+            let mut attempts = 0;
+            let fake_name = loop {
+                let candidate = match kind {
+                    UnidentifiedKind::Scroll => scroll_name(rng),
+                    UnidentifiedKind::Potion => potion_name(rng, attempts),
+                };
+                if used.insert(candidate.clone()) {
+                    break candidate;
+                }
+                attempts += 1;
+            };
+            unidentified_names.insert(name, fake_name);
+        }
+
+        IdentificationTable {
+            unidentified_names,
+            identified: HashSet::new(),
+        }
+    }
+
+    /// What the player knows the item as
+    pub fn display_name(&self, real_name: &str) -> String {
+        match self.unidentified_names.get(real_name) {
+            Some(fake_name) if !self.identified.contains(real_name) => fake_name.clone(),
+            _ => real_name.to_string(),
+        }
+    }
+
+    /// Reveals an item's real name for the rest of the run. Returns true the
+    /// first time an unidentified item is worked out.
+    pub fn identify(&mut self, real_name: &str) -> bool {
+        self.unidentified_names.contains_key(real_name)
+            && self.identified.insert(real_name.to_string())
+    }
+}
+
+fn scroll_name(rng: &mut RandomNumberGenerator) -> String {
+    let syllables = rng.roll_dice(2, 2);
+    let title: String = (0..syllables)
+        .map(|_| *rng.random_slice_entry(SCROLL_SYLLABLES).unwrap())
+        .collect();
+    format!("scroll titled {}", title.to_uppercase())
+}
+
+/// Falls back to two adjectives when one keeps coming up taken, so there
+/// is always a free name even with more potions than adjectives
+fn potion_name(rng: &mut RandomNumberGenerator, attempts: usize) -> String {
+    let adjective = rng.random_slice_entry(POTION_ADJECTIVES).unwrap();
+    if attempts < POTION_ADJECTIVES.len() {
+        format!("{} potion", adjective)
+    } else {
+        let second = rng.random_slice_entry(POTION_ADJECTIVES).unwrap();
+        format!("{}, {} potion", adjective, second)
+    }
+}
@@ -1,9 +1,10 @@
 extern crate rltk;
 extern crate serde;
-use rltk::{Console, GameState, Point, Rltk};
+use rltk::{Console, GameState, Point, RandomNumberGenerator, Rltk};
 extern crate specs;
 use specs::prelude::*;
 use specs::saveload::{SimpleMarker, SimpleMarkerAllocator};
+use std::time::{Duration, Instant};
 #[macro_use]
 extern crate specs_derive;
 
@@ -11,14 +12,24 @@ mod components;
 pub use components::*;
 mod map;
 pub use map::*;
+mod dungeon;
+mod flow_field;
+mod map_builders;
+use dungeon::MasterDungeonMap;
 mod player;
 mod random_table;
+mod raws;
 mod rect;
 pub use rect::Rect;
+mod save_format;
+mod seed;
+use seed::WorldSeed;
 mod systems;
 use systems::*;
 mod gamelog;
 mod gui;
+mod identification;
+mod morgue;
 mod spawner;
 
 #[derive(PartialEq, Copy, Clone)]
@@ -26,36 +37,171 @@ pub enum RunState {
     AwaitingInput,
     PreRun,
     PlayerTurn,
-    MonsterTurn,
+    Ticking,
     ShowInventory,
     ShowDropItem,
     ShowTargeting {
         range: i32,
         item: Entity,
     },
+    ShowFiring {
+        range: i32,
+    },
     MainMenu {
         menu_selection: gui::MainMenuSelection,
     },
+    SaveSlots {
+        mode: gui::SlotMenuMode,
+        selection: usize,
+        confirming: bool,
+    },
+    EnterSeed {
+        seed: u64,
+    },
+    ChooseMode {
+        seed: u64,
+        mode: saveload_system::GameMode,
+    },
     SaveGame,
     NextLevel,
+    PreviousLevel,
     ShowUnequip,
+    LevelUp,
+    MagicMapReveal {
+        row: i32,
+    },
+    ShowLog {
+        scroll: usize,
+        filter: gui::LogFilter,
+    },
     GameOver,
 }
 
 pub struct State {
     ecs: World,
+    /// Seed given on the command line, used for every new game started from the menu
+    requested_seed: Option<u64>,
+    /// Why the last save couldn't be loaded, shown under the main menu
+    menu_error: Option<String>,
+    /// Slot the current run has claimed. Autosaves and permadeath only ever
+    /// touch this one, and a run that found every slot taken has none until
+    /// the player saves it somewhere.
+    save_slot: Option<usize>,
+    /// What was in each slot when the slot screen was opened
+    save_slots: Vec<saveload_system::SaveSlot>,
+    last_autosave: Instant,
+    /// Set on reaching a new level, so the save happens once it has settled
+    autosave_due: bool,
+    /// How the last run ended, for the game over screen
+    death_recap: Option<morgue::DeathRecap>,
 }
 
+/// How long play goes on between autosaves
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
 impl State {
+    /// Registers every component and sets up the resources the game runs on
+    fn new(requested_seed: Option<u64>) -> State {
+        let mut gs = State {
+            ecs: World::new(),
+            requested_seed,
+            menu_error: None,
+            save_slot: None,
+            save_slots: Vec::new(),
+            last_autosave: Instant::now(),
+            autosave_due: false,
+            death_recap: None,
+        };
+
+        // Register all components
+        gs.ecs.register::<Position>();
+        gs.ecs.register::<Renderable>();
+        gs.ecs.register::<Player>();
+        gs.ecs.register::<Viewshed>();
+        gs.ecs.register::<Monster>();
+        gs.ecs.register::<OtherLevelPosition>();
+        gs.ecs.register::<Name>();
+        gs.ecs.register::<BlocksTile>();
+        gs.ecs.register::<CombatStats>();
+        gs.ecs.register::<WantsToMelee>();
+        gs.ecs.register::<SufferDamage>();
+        gs.ecs.register::<Item>();
+        gs.ecs.register::<ProvidesHealing>();
+        gs.ecs.register::<InBackpack>();
+        gs.ecs.register::<WantsToPickupItem>();
+        gs.ecs.register::<WantsToUseItem>();
+        gs.ecs.register::<WantsToDropItem>();
+        gs.ecs.register::<Consumable>();
+        gs.ecs.register::<Ranged>();
+        gs.ecs.register::<InflictsDamage>();
+        gs.ecs.register::<AreaOfEffect>();
+        gs.ecs.register::<StatusEffects>();
+        gs.ecs.register::<AppliesStatus>();
+        gs.ecs.register::<Equippable>();
+        gs.ecs.register::<Equipped>();
+        gs.ecs.register::<MeleeWeapon>();
+        gs.ecs.register::<DefenseBonus>();
+        gs.ecs.register::<RangedWeapon>();
+        gs.ecs.register::<Ammunition>();
+        gs.ecs.register::<WantsToShoot>();
+        gs.ecs.register::<WantsToUnequipItem>();
+        gs.ecs.register::<ParticleLifetime>();
+        gs.ecs.register::<HungerClock>();
+        gs.ecs.register::<ProvidesFood>();
+        gs.ecs.register::<MagicMapper>();
+        gs.ecs.register::<MonsterBrain>();
+        gs.ecs.register::<ProvidesClairvoyance>();
+        gs.ecs.register::<SlainByPlayer>();
+        gs.ecs.register::<Experience>();
+        gs.ecs.register::<Energy>();
+        gs.ecs.register::<Door>();
+        gs.ecs.register::<Hidden>();
+        gs.ecs.register::<EntryTrigger>();
+        gs.ecs.register::<SingleActivation>();
+        gs.ecs.register::<EntityMoved>();
+        gs.ecs.register::<MyTurn>();
+        gs.ecs.register::<SimpleMarker<SerializeMe>>();
+        gs.ecs.register::<SerializationHelper>();
+
+        gs.ecs.insert(RandomNumberGenerator::new());
+        gs.ecs.insert(SimpleMarkerAllocator::<SerializeMe>::new());
+        gs.ecs.insert(Map::default());
+        gs.ecs.insert(raws::load_raws());
+
+        // Build a world up front so every resource exists while the menu is open
+        gs.new_game(gs.seed_for_new_game(), saveload_system::GameMode::default());
+        gs.ecs.insert(RunState::MainMenu {
+            menu_selection: gui::MainMenuSelection::NewGame,
+        });
+        gs.ecs.insert(particle_system::ParticleBuilder::new());
+        gs.ecs.insert(initiative_system::GameClock::default());
+        gs.ecs.insert(player::TravelPath::default());
+        gs.ecs.insert(player::AutoExplore::default());
+        gs.ecs.insert(flow_field_system::PlayerFlowFields::new());
+        gs
+    }
+
     fn run_systems(&mut self) {
+        let mut initiative = initiative_system::InitiativeSystem {};
+        initiative.run_now(&self.ecs);
+        let mut mapindex = map_indexing_system::MapIndexingSystem {};
+        mapindex.run_now(&self.ecs);
+        let mut flow_fields = flow_field_system::FlowFieldSystem {};
+        flow_fields.run_now(&self.ecs);
         let mut vis = visibility_system::VisibilitySystem {};
         vis.run_now(&self.ecs);
+        let mut status = status_effect_system::StatusEffectSystem {};
+        status.run_now(&self.ecs);
         let mut mob = monster_ai_system::MonsterAI {};
         mob.run_now(&self.ecs);
-        let mut mapindex = map_indexing_system::MapIndexingSystem {};
-        mapindex.run_now(&self.ecs);
+        let mut triggers = trigger_system::TriggerSystem {};
+        triggers.run_now(&self.ecs);
         let mut melee = melee_combat_system::MeleeCombatSystem {};
         melee.run_now(&self.ecs);
+        let mut ranged = ranged_combat_system::RangedCombatSystem {};
+        ranged.run_now(&self.ecs);
+        let mut hunger = hunger_system::HungerSystem {};
+        hunger.run_now(&self.ecs);
         let mut damage = damage_system::DamageSystem {};
         damage.run_now(&self.ecs);
         let mut pickup = inventory_system::ItemCollectionSystem {};
@@ -71,69 +217,56 @@ impl State {
         self.ecs.maintain();
     }
 
-    fn entities_to_remove_on_level_change(&mut self) -> Vec<Entity> {
-        let entities = self.ecs.entities();
-        let player = self.ecs.read_storage::<Player>();
-        let backpack = self.ecs.read_storage::<InBackpack>();
-        let equipped_items = self.ecs.read_storage::<Equipped>();
-        let player_entity = self.ecs.fetch::<Entity>();
-
-        let mut to_delete: Vec<Entity> = Vec::new();
-        for entity in entities.join() {
-            let mut should_delete = true;
-
-            // Don't delete the player
-            let p = player.get(entity);
-            if let Some(_p) = p {
-                should_delete = false;
-            }
-
-            // Don't delete the player's equipment
-            let bp = backpack.get(entity);
-            if let Some(bp) = bp {
-                if bp.owner == *player_entity {
-                    should_delete = false;
-                }
-            }
+    /// Moves the player up or down by the given number of levels. Levels
+    /// that were visited before are restored exactly as they were left.
+    fn change_level(&mut self, offset: i32) {
+        // Put the level being left into storage
+        dungeon::freeze_level_entities(&mut self.ecs);
+        let current_depth;
+        {
+            let worldmap = self.ecs.fetch::<Map>();
+            current_depth = worldmap.depth;
+            let mut dungeon_master = self.ecs.write_resource::<MasterDungeonMap>();
+            dungeon_master.store_map(&worldmap);
+        }
 
-            let equipped = equipped_items.get(entity);
-            if let Some(item) = equipped {
-                if item.owner == *player_entity {
-                    should_delete = false;
-                }
+        // Restore the new level, or build it if this is the first visit
+        let new_depth = current_depth + offset;
+        {
+            let mut run_stats = self.ecs.write_resource::<morgue::RunStats>();
+            run_stats.deepest = i32::max(run_stats.deepest, new_depth);
+        }
+        let stored_map = self.ecs.fetch::<MasterDungeonMap>().get_map(new_depth);
+        let first_visit = stored_map.is_none();
+        match stored_map {
+            Some(stored_map) => {
+                *self.ecs.write_resource::<Map>() = stored_map;
+                dungeon::thaw_level_entities(&mut self.ecs, new_depth);
             }
-
-            if should_delete {
-                to_delete.push(entity);
+            None => {
+                self.generate_level(new_depth);
             }
         }
 
-        to_delete
-    }
-
-    fn goto_next_level(&mut self) {
-        // Delete entities that aren't the player or his/her equipment
-        let to_delete = self.entities_to_remove_on_level_change();
-        for target in to_delete {
-            self.ecs
-                .delete_entity(target)
-                .expect("Unable to delete entity");
-        }
-        // Build a new map and place the player
-        let worldmap;
-        let current_depth;
+        // Arrive on the stairs leading back to where the player came from
+        let arrival_tile = if offset > 0 {
+            TileType::UpStairs
+        } else {
+            TileType::DownStairs
+        };
+        let (player_x, player_y);
         {
-            let mut worldmap_resource = self.ecs.write_resource::<Map>();
-            current_depth = worldmap_resource.depth;
-            *worldmap_resource = Map::new_map_rooms_and_corridors(current_depth + 1);
-            worldmap = worldmap_resource.clone();
-        }
-        // Spawn bad guys
-        for room in worldmap.rooms.iter().skip(1) {
-            spawner::spawn_room(&mut self.ecs, room, current_depth + 1);
+            let worldmap = self.ecs.fetch::<Map>();
+            let arrival_idx = worldmap
+                .tiles
+                .iter()
+                .position(|tile| *tile == arrival_tile)
+                .expect("Level has no stairs to arrive on") as i32;
+            player_x = arrival_idx % worldmap.width;
+            player_y = arrival_idx / worldmap.width;
         }
+
         // Place the player and update resources
-        let (player_x, player_y) = worldmap.rooms[0].center();
         let mut player_position = self.ecs.write_resource::<Point>();
         *player_position = Point::new(player_x, player_y);
         let mut position_components = self.ecs.write_storage::<Position>();
@@ -149,19 +282,142 @@ impl State {
         if let Some(vs) = vs {
             vs.dirty = true;
         }
-        // Notify the player and give them some health
+
+        let mut gamelog = self.ecs.fetch_mut::<gamelog::GameLog>();
+        if offset < 0 {
+            gamelog.system("You climb back up the stairs.".to_string());
+        } else if !first_visit {
+            gamelog.system("You return to a level you have been to before.".to_string());
+        } else {
+            // Notify the player and give them some health
+            gamelog.system("You descend to the next level, and take a moment to heal.".to_string());
+            let mut player_health_store = self.ecs.write_storage::<CombatStats>();
+            let player_health = player_health_store.get_mut(*player_entity);
+            if let Some(player_health) = player_health {
+                player_health.hp = i32::max(player_health.hp, player_health.max_hp / 2);
+            }
+        }
+    }
+
+    fn level_up_due(&self) -> bool {
+        let player_entity = self.ecs.fetch::<Entity>();
+        let experience = self.ecs.read_storage::<Experience>();
+        experience
+            .get(*player_entity)
+            .is_some_and(|xp| xp.level_up_due())
+    }
+
+    /// Raises the player's level, spending the experience it took and
+    /// applying the chosen improvement. Leveling up also heals fully.
+    fn level_up(&mut self, choice: gui::LevelUpChoice) {
+        let player_entity = self.ecs.fetch::<Entity>();
+        let mut experience = self.ecs.write_storage::<Experience>();
+        let mut combat_stats = self.ecs.write_storage::<CombatStats>();
         let mut gamelog = self.ecs.fetch_mut::<gamelog::GameLog>();
-        gamelog
-            .entries
-            .push("You descend to the next level, and take a moment to heal.".to_string());
-        let mut player_health_store = self.ecs.write_storage::<CombatStats>();
-        let player_health = player_health_store.get_mut(*player_entity);
-        if let Some(player_health) = player_health {
-            player_health.hp = i32::max(player_health.hp, player_health.max_hp / 2);
+
+        if let Some(xp) = experience.get_mut(*player_entity) {
+            xp.xp -= xp.xp_to_next_level();
+            xp.level += 1;
+            gamelog.system(format!("Welcome to level {}!", xp.level));
+        }
+        if let Some(stats) = combat_stats.get_mut(*player_entity) {
+            match choice {
+                gui::LevelUpChoice::Health => stats.max_hp += 10,
+                gui::LevelUpChoice::Power => stats.power += 1,
+                gui::LevelUpChoice::Defense => stats.defense += 1,
+            }
+            stats.hp = stats.max_hp;
+        }
+    }
+
+    /// The seed given on the command line, or a random one
+    fn seed_for_new_game(&self) -> u64 {
+        self.requested_seed
+            .unwrap_or_else(|| WorldSeed::random().seed)
+    }
+
+    /// Loads the run in the given slot. Permadeath runs only get loaded the
+    /// once, so their save goes as soon as it has been read.
+    fn load_slot(&mut self, slot: usize) -> RunState {
+        match saveload_system::load_game(&mut self.ecs, slot) {
+            Ok(()) => {
+                self.menu_error = None;
+                self.save_slot = Some(slot);
+                self.last_autosave = Instant::now();
+                self.autosave_due = false;
+                if *self.ecs.fetch::<saveload_system::GameMode>()
+                    == saveload_system::GameMode::Permadeath
+                {
+                    saveload_system::delete_save(slot);
+                }
+                RunState::AwaitingInput
+            }
+            Err(e) => {
+                self.menu_error = Some(format!("Unable to load: {}", e));
+                RunState::MainMenu {
+                    menu_selection: gui::MainMenuSelection::LoadGame,
+                }
+            }
+        }
+    }
+
+    /// Saves the run and quits to the menu, or stays in the game if the
+    /// save fails rather than lose it
+    fn save_and_quit(&mut self, slot: usize) -> RunState {
+        match saveload_system::save_game(&mut self.ecs, slot) {
+            Ok(()) => {
+                self.save_slot = Some(slot);
+                RunState::MainMenu {
+                    menu_selection: gui::MainMenuSelection::LoadGame,
+                }
+            }
+            Err(e) => {
+                self.ecs
+                    .write_resource::<gamelog::GameLog>()
+                    .system(format!("Unable to save: {}", e));
+                RunState::AwaitingInput
+            }
+        }
+    }
+
+    /// Saves the run to its slot without leaving the game. A run without a
+    /// slot isn't saved, rather than overwrite someone else's.
+    fn autosave(&mut self) {
+        self.last_autosave = Instant::now();
+        self.autosave_due = false;
+        let slot = match self.save_slot {
+            Some(slot) => slot,
+            None => return,
+        };
+        if let Err(e) = saveload_system::save_game(&mut self.ecs, slot) {
+            self.ecs
+                .write_resource::<gamelog::GameLog>()
+                .system(format!("Unable to autosave: {}", e));
+        }
+    }
+
+    /// Writes the morgue file, then takes a permadeath run's save with the
+    /// player. A casual run keeps its last checkpoint.
+    fn player_died(&mut self) {
+        let mut recap = morgue::DeathRecap::new(&self.ecs);
+        recap.morgue_file = morgue::write_morgue(&recap).ok();
+        self.death_recap = Some(recap);
+
+        if *self.ecs.fetch::<saveload_system::GameMode>() == saveload_system::GameMode::Permadeath {
+            if let Some(slot) = self.save_slot {
+                saveload_system::delete_save(slot);
+            }
         }
     }
 
-    fn game_over_cleanup(&mut self) {
+    /// Wipes the world and starts a fresh run from the given seed
+    fn new_game(&mut self, seed: u64, mode: saveload_system::GameMode) {
+        self.menu_error = None;
+        self.death_recap = None;
+        self.last_autosave = Instant::now();
+        self.autosave_due = false;
+        // A slot is only claimed once the player starts playing
+        self.save_slot = None;
         // Delete everything
         let mut to_delete = Vec::new();
         for e in self.ecs.entities().join() {
@@ -170,38 +426,76 @@ impl State {
         for del in to_delete.iter() {
             self.ecs.delete_entity(*del).expect("Deletion failed");
         }
-        // Build a new map and place the player
-        let worldmap;
+
+        self.ecs.insert(mode);
+        self.ecs.insert(morgue::RunStats {
+            deepest: 1,
+            ..Default::default()
+        });
+        let world_seed = WorldSeed::new(seed);
+        let identification = identification::IdentificationTable::new(
+            &self.ecs.fetch::<raws::RawMaster>(),
+            &mut world_seed.identification_rng(),
+        );
+        self.ecs.insert(identification);
+        self.ecs.insert(world_seed);
+        self.ecs.insert(MasterDungeonMap::new());
+        let start = self.generate_level(1);
+
+        // Place the player and update resources
+        let player_entity = spawner::player(&mut self.ecs, start.x, start.y);
+        self.ecs.insert(player_entity);
+        self.ecs.insert(Point::new(start.x, start.y));
+        self.ecs.insert(gamelog::GameLog::new(format!(
+            "Welcome to Roguelike! (seed {})",
+            seed
+        )));
+    }
+
+    /// Claims a free slot for the run the player has just started, if there
+    /// is one, for autosaves to go to
+    fn claim_free_slot(&mut self) {
+        self.save_slot = saveload_system::save_slots()
+            .iter()
+            .position(|slot| matches!(slot, saveload_system::SaveSlot::Empty));
+        if self.save_slot.is_none() {
+            self.ecs.write_resource::<gamelog::GameLog>().system(
+                "Every save slot is taken, so there is no autosave until you save the game."
+                    .to_string(),
+            );
+        }
+    }
+
+    /// Builds the map for the given depth from the world seed and fills it
+    /// with monsters and items. The random number generator is reseeded for
+    /// the level, so a seed always recreates the same dungeon. Returns where
+    /// the player should start.
+    fn generate_level(&mut self, depth: i32) -> Position {
+        let level_rng = self.ecs.fetch::<WorldSeed>().level_rng(depth);
+        let mut builder;
         {
+            let mut rng = self.ecs.write_resource::<RandomNumberGenerator>();
+            *rng = level_rng;
+            builder = map_builders::random_builder(depth, &mut rng);
+            builder.build_map(&mut rng);
             let mut worldmap_resource = self.ecs.write_resource::<Map>();
-            *worldmap_resource = Map::new_map_rooms_and_corridors(1);
-            worldmap = worldmap_resource.clone();
+            *worldmap_resource = builder.get_map();
+            // Every level below the first starts on the way back up
+            if depth > 1 {
+                let start = builder.get_starting_position();
+                let start_idx = worldmap_resource.xy_idx(start.x, start.y);
+                worldmap_resource.tiles[start_idx] = TileType::UpStairs;
+            }
         }
         // Spawn bad guys
-        for room in worldmap.rooms.iter().skip(1) {
-            spawner::spawn_room(&mut self.ecs, room, 1);
-        }
-
-        // Place the player and update resources
-        let (player_x, player_y) = worldmap.rooms[0].center();
-        let player_entity = spawner::player(&mut self.ecs, player_x, player_y);
-        let mut player_position = self.ecs.write_resource::<Point>();
-        *player_position = Point::new(player_x, player_y);
-        let mut position_components = self.ecs.write_storage::<Position>();
-        let mut player_entity_writer = self.ecs.write_resource::<Entity>();
-        *player_entity_writer = player_entity;
-        let player_pos_comp = position_components.get_mut(player_entity);
-        if let Some(player_pos_comp) = player_pos_comp {
-            player_pos_comp.x = player_x;
-            player_pos_comp.y = player_y;
+        for region in builder.get_spawn_regions().iter() {
+            spawner::spawn_region(&mut self.ecs, region, depth);
         }
-
-        // Mark the player's visibility as dirty
-        let mut viewshed_components = self.ecs.write_storage::<Viewshed>();
-        let vs = viewshed_components.get_mut(player_entity);
-        if let Some(vs) = vs {
-            vs.dirty = true;
+        for idx in builder.get_door_positions() {
+            let width = self.ecs.fetch::<Map>().width;
+            spawner::door(&mut self.ecs, idx as i32 % width, idx as i32 / width);
         }
+        builder.get_starting_position()
     }
 }
 
@@ -215,23 +509,46 @@ impl GameState for State {
             let runstate = self.ecs.fetch::<RunState>();
             newrunstate = *runstate;
         }
+        let previous_runstate = newrunstate;
 
         match newrunstate {
-            RunState::MainMenu { .. } => {}
+            RunState::MainMenu { .. }
+            | RunState::EnterSeed { .. }
+            | RunState::ChooseMode { .. }
+            | RunState::SaveSlots { .. } => {}
             _ => {
                 map::draw_map(&self.ecs, ctx);
 
                 {
                     let positions = self.ecs.read_storage::<Position>();
                     let renderables = self.ecs.read_storage::<Renderable>();
+                    let hidden = self.ecs.read_storage::<Hidden>();
+                    let monsters = self.ecs.read_storage::<Monster>();
                     let map = self.ecs.fetch::<map::Map>();
-
-                    let mut data = (&positions, &renderables).join().collect::<Vec<_>>();
-                    data.sort_by(|&a, &b| b.1.render_order.cmp(&a.1.render_order));
-                    for (pos, render) in data.iter() {
+                    let entities = self.ecs.entities();
+                    let clairvoyant =
+                        player::player_has_status(&self.ecs, StatusEffectType::Clairvoyance);
+
+                    let mut data = (&entities, &positions, &renderables, !&hidden)
+                        .join()
+                        .map(|(entity, pos, render, _hidden)| (entity, pos, render))
+                        .collect::<Vec<_>>();
+                    data.sort_by_key(|&(_entity, _pos, render)| {
+                        std::cmp::Reverse(render.render_order)
+                    });
+                    for (entity, pos, render) in data.iter() {
                         let idx = map.xy_idx(pos.x, pos.y);
                         if map.visible_tiles[idx] {
                             ctx.set(pos.x, pos.y, render.fg, render.bg, render.glyph)
+                        } else if clairvoyant && monsters.get(*entity).is_some() {
+                            // Sensed monsters show up even through walls
+                            ctx.set(
+                                pos.x,
+                                pos.y,
+                                rltk::RGB::named(rltk::LIGHT_BLUE),
+                                render.bg,
+                                render.glyph,
+                            )
                         }
                     }
                     gui::draw_ui(&self.ecs, ctx);
@@ -246,17 +563,35 @@ impl GameState for State {
                 newrunstate = RunState::AwaitingInput;
             }
             RunState::AwaitingInput => {
-                newrunstate = player::player_input(self, ctx);
+                if self.autosave_due || self.last_autosave.elapsed() >= AUTOSAVE_INTERVAL {
+                    self.autosave();
+                }
+                if self.level_up_due() {
+                    newrunstate = RunState::LevelUp;
+                } else {
+                    newrunstate = player::player_input(self, ctx);
+                }
             }
             RunState::PlayerTurn => {
+                self.ecs.write_resource::<gamelog::GameLog>().turn += 1;
                 self.run_systems();
                 self.ecs.maintain();
-                newrunstate = RunState::MonsterTurn;
+                // Using a magic mapper hands over to the reveal before the
+                // rest of the world gets to act
+                newrunstate = match *self.ecs.fetch::<RunState>() {
+                    RunState::MagicMapReveal { .. } => RunState::MagicMapReveal { row: 0 },
+                    _ => RunState::Ticking,
+                };
             }
-            RunState::MonsterTurn => {
-                self.run_systems();
-                self.ecs.maintain();
-                newrunstate = RunState::AwaitingInput;
+            RunState::Ticking => {
+                // Let everything else act until the player has enough energy
+                // for another turn, or dies trying
+                while newrunstate == RunState::Ticking {
+                    self.run_systems();
+                    self.ecs.maintain();
+                    damage_system::delete_the_dead(&mut self.ecs);
+                    newrunstate = *self.ecs.fetch::<RunState>();
+                }
             }
             RunState::ShowInventory => {
                 let result = gui::show_inventory(self, ctx);
@@ -326,6 +661,25 @@ impl GameState for State {
                     }
                 }
             }
+            RunState::ShowFiring { range } => {
+                let target = gui::ranged_target(self, ctx, range);
+                match target.0 {
+                    gui::ItemMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::Selected => {
+                        let mut intent = self.ecs.write_storage::<WantsToShoot>();
+                        intent
+                            .insert(
+                                *self.ecs.fetch::<Entity>(),
+                                WantsToShoot {
+                                    target: target.1.unwrap(),
+                                },
+                            )
+                            .expect("Unable to insert intent");
+                        newrunstate = RunState::PlayerTurn;
+                    }
+                }
+            }
             RunState::MainMenu { .. } => {
                 let result = gui::main_menu(self, ctx);
 
@@ -336,10 +690,29 @@ impl GameState for State {
                         }
                     }
                     gui::MainMenuResult::Selected { selected } => match selected {
-                        gui::MainMenuSelection::NewGame => newrunstate = RunState::PreRun,
+                        gui::MainMenuSelection::NewGame => {
+                            newrunstate = RunState::ChooseMode {
+                                seed: self.seed_for_new_game(),
+                                mode: saveload_system::GameMode::default(),
+                            };
+                        }
+                        gui::MainMenuSelection::SeededGame => {
+                            newrunstate = RunState::EnterSeed {
+                                seed: self.requested_seed.unwrap_or(0),
+                            }
+                        }
                         gui::MainMenuSelection::LoadGame => {
-                            saveload_system::load_game(&mut self.ecs);
-                            newrunstate = RunState::AwaitingInput;
+                            self.save_slots = saveload_system::save_slots();
+                            let selection = self
+                                .save_slots
+                                .iter()
+                                .position(|slot| !matches!(slot, saveload_system::SaveSlot::Empty))
+                                .unwrap_or(0);
+                            newrunstate = RunState::SaveSlots {
+                                mode: gui::SlotMenuMode::Load,
+                                selection,
+                                confirming: false,
+                            };
                         }
                         gui::MainMenuSelection::Quit => {
                             ::std::process::exit(0);
@@ -347,14 +720,118 @@ impl GameState for State {
                     },
                 }
             }
+            RunState::EnterSeed { seed } => {
+                let result = gui::seed_entry(ctx, seed);
+                match result {
+                    gui::SeedEntryResult::Editing { seed } => {
+                        newrunstate = RunState::EnterSeed { seed }
+                    }
+                    gui::SeedEntryResult::Cancel => {
+                        newrunstate = RunState::MainMenu {
+                            menu_selection: gui::MainMenuSelection::SeededGame,
+                        }
+                    }
+                    gui::SeedEntryResult::Confirmed { seed } => {
+                        newrunstate = RunState::ChooseMode {
+                            seed,
+                            mode: saveload_system::GameMode::default(),
+                        };
+                    }
+                }
+            }
+            RunState::ChooseMode { seed, mode } => match gui::choose_mode(ctx, mode) {
+                gui::ModeMenuResult::Choosing { mode } => {
+                    newrunstate = RunState::ChooseMode { seed, mode };
+                }
+                gui::ModeMenuResult::Chosen { mode } => {
+                    self.new_game(seed, mode);
+                    self.claim_free_slot();
+                    newrunstate = RunState::PreRun;
+                }
+                gui::ModeMenuResult::Cancel => {
+                    newrunstate = RunState::MainMenu {
+                        menu_selection: gui::MainMenuSelection::NewGame,
+                    };
+                }
+            },
             RunState::SaveGame => {
-                saveload_system::save_game(&mut self.ecs);
-                newrunstate = RunState::MainMenu {
-                    menu_selection: gui::MainMenuSelection::LoadGame,
-                };
+                let permadeath = *self.ecs.fetch::<saveload_system::GameMode>()
+                    == saveload_system::GameMode::Permadeath;
+                match self.save_slot {
+                    // Permadeath runs only ever have the one save
+                    Some(slot) if permadeath => newrunstate = self.save_and_quit(slot),
+                    _ => {
+                        self.save_slots = saveload_system::save_slots();
+                        newrunstate = RunState::SaveSlots {
+                            mode: gui::SlotMenuMode::Save,
+                            selection: self.save_slot.unwrap_or(0),
+                            confirming: false,
+                        };
+                    }
+                }
+            }
+            RunState::SaveSlots {
+                mode,
+                selection,
+                confirming,
+            } => {
+                let result = gui::save_slot_menu(
+                    ctx,
+                    mode,
+                    selection,
+                    confirming,
+                    &self.save_slots,
+                    self.save_slot,
+                );
+                match result {
+                    gui::SlotMenuResult::Selecting {
+                        selection,
+                        confirming,
+                    } => {
+                        newrunstate = RunState::SaveSlots {
+                            mode,
+                            selection,
+                            confirming,
+                        }
+                    }
+                    gui::SlotMenuResult::Delete { slot } => {
+                        saveload_system::delete_save(slot);
+                        self.save_slots = saveload_system::save_slots();
+                        newrunstate = if saveload_system::does_save_exist() {
+                            RunState::SaveSlots {
+                                mode,
+                                selection,
+                                confirming: false,
+                            }
+                        } else {
+                            RunState::MainMenu {
+                                menu_selection: gui::MainMenuSelection::NewGame,
+                            }
+                        };
+                    }
+                    gui::SlotMenuResult::Cancel => {
+                        newrunstate = match mode {
+                            gui::SlotMenuMode::Load => RunState::MainMenu {
+                                menu_selection: gui::MainMenuSelection::LoadGame,
+                            },
+                            gui::SlotMenuMode::Save => RunState::AwaitingInput,
+                        };
+                    }
+                    gui::SlotMenuResult::Chosen { slot } => {
+                        newrunstate = match mode {
+                            gui::SlotMenuMode::Load => self.load_slot(slot),
+                            gui::SlotMenuMode::Save => self.save_and_quit(slot),
+                        };
+                    }
+                }
             }
             RunState::NextLevel => {
-                self.goto_next_level();
+                self.change_level(1);
+                self.autosave_due = true;
+                newrunstate = RunState::PreRun;
+            }
+            RunState::PreviousLevel => {
+                self.change_level(-1);
                 newrunstate = RunState::PreRun;
             }
             RunState::ShowUnequip => {
@@ -376,12 +853,51 @@ impl GameState for State {
                     }
                 }
             }
+            RunState::LevelUp => {
+                let result = gui::level_up_menu(ctx);
+                match result {
+                    gui::LevelUpResult::NoSelection => {}
+                    gui::LevelUpResult::Selected { choice } => {
+                        self.level_up(choice);
+                        newrunstate = RunState::AwaitingInput;
+                    }
+                }
+            }
+            RunState::MagicMapReveal { row } => {
+                let mut map = self.ecs.fetch_mut::<Map>();
+                for x in 0..map.width {
+                    let idx = map.xy_idx(x, row);
+                    map.revealed_tiles[idx] = true;
+                }
+                if row + 1 >= map.height {
+                    newrunstate = RunState::Ticking;
+                } else {
+                    newrunstate = RunState::MagicMapReveal { row: row + 1 };
+                }
+            }
+            RunState::ShowLog { scroll, filter } => {
+                newrunstate = match gui::show_log(&self.ecs, ctx, scroll, filter) {
+                    gui::LogViewerResult::Close => RunState::AwaitingInput,
+                    gui::LogViewerResult::Viewing { scroll, filter } => {
+                        RunState::ShowLog { scroll, filter }
+                    }
+                };
+            }
             RunState::GameOver => {
-                let result = gui::game_over(ctx);
+                let mode = *self.ecs.fetch::<saveload_system::GameMode>();
+                let checkpoint = mode == saveload_system::GameMode::Casual
+                    && self.save_slot.is_some_and(saveload_system::save_exists);
+                let result = gui::game_over(ctx, checkpoint, self.death_recap.as_ref());
                 match result {
                     gui::GameOverResult::NoSelection => {}
+                    gui::GameOverResult::LoadCheckpoint => {
+                        if let Some(slot) = self.save_slot {
+                            newrunstate = self.load_slot(slot);
+                        }
+                    }
                     gui::GameOverResult::QuitToMenu => {
-                        self.game_over_cleanup();
+                        // Replace the dead player's world so the menu starts from a clean slate
+                        self.new_game(self.seed_for_new_game(), mode);
                         newrunstate = RunState::MainMenu {
                             menu_selection: gui::MainMenuSelection::NewGame,
                         };
@@ -396,6 +912,11 @@ impl GameState for State {
         }
 
         damage_system::delete_the_dead(&mut self.ecs);
+        if previous_runstate != RunState::GameOver
+            && *self.ecs.fetch::<RunState>() == RunState::GameOver
+        {
+            self.player_died();
+        }
     }
 }
 
@@ -403,64 +924,46 @@ fn main() {
     use rltk::RltkBuilder;
     let context = RltkBuilder::simple80x50().with_title("Roguelike").build();
 
-    let mut gs = State { ecs: World::new() };
-
-    // Register all components
-    gs.ecs.register::<Position>();
-    gs.ecs.register::<Renderable>();
-    gs.ecs.register::<Player>();
-    gs.ecs.register::<Viewshed>();
-    gs.ecs.register::<Monster>();
-    gs.ecs.register::<Name>();
-    gs.ecs.register::<BlocksTile>();
-    gs.ecs.register::<CombatStats>();
-    gs.ecs.register::<WantsToMelee>();
-    gs.ecs.register::<SufferDamage>();
-    gs.ecs.register::<Item>();
-    gs.ecs.register::<ProvidesHealing>();
-    gs.ecs.register::<InBackpack>();
-    gs.ecs.register::<WantsToPickupItem>();
-    gs.ecs.register::<WantsToUseItem>();
-    gs.ecs.register::<WantsToDropItem>();
-    gs.ecs.register::<Consumable>();
-    gs.ecs.register::<Ranged>();
-    gs.ecs.register::<InflictsDamage>();
-    gs.ecs.register::<AreaOfEffect>();
-    gs.ecs.register::<Confusion>();
-    gs.ecs.register::<Equippable>();
-    gs.ecs.register::<Equipped>();
-    gs.ecs.register::<MeleePowerBonus>();
-    gs.ecs.register::<DefenseBonus>();
-    gs.ecs.register::<WantsToUnequipItem>();
-    gs.ecs.register::<ParticleLifetime>();
-    gs.ecs.register::<SimpleMarker<SerializeMe>>();
-    gs.ecs.register::<SerializationHelper>();
-
-    gs.ecs.insert(rltk::RandomNumberGenerator::new());
-    gs.ecs.insert(SimpleMarkerAllocator::<SerializeMe>::new());
-
-    let map = map::Map::new_map_rooms_and_corridors(1);
-    let (player1_x, player1_y) = map.rooms[0].center();
-    // Create player
-    let player_entity = spawner::player(&mut gs.ecs, player1_x, player1_y);
-    // Insert player into GameState
-    gs.ecs.insert(player_entity);
-    // Insert player location to GameState
-    gs.ecs.insert(Point::new(player1_x, player1_y));
-
-    // Add monsters to each room
-    for room in map.rooms.iter().skip(1) {
-        spawner::spawn_room(&mut gs.ecs, room, 1);
+    let mut gs = State::new(seed::seed_from_args());
+    // Saves from before there were slots are moved into one
+    if let Err(e) = saveload_system::import_legacy_save() {
+        gs.menu_error = Some(format!("Unable to bring over the old save: {}", e));
     }
-
-    // Insert Map to GameState
-    gs.ecs.insert(map);
-    gs.ecs.insert(RunState::MainMenu {
-        menu_selection: gui::MainMenuSelection::NewGame,
-    });
-    gs.ecs.insert(gamelog::GameLog {
-        entries: vec!["Welcome to Roguelike".to_string()],
-    });
-    gs.ecs.insert(particle_system::ParticleBuilder::new());
     rltk::main_loop(context, gs);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The name and position of everything on the level, in entity order
+    fn level_contents(gs: &State) -> Vec<(String, i32, i32)> {
+        let names = gs.ecs.read_storage::<Name>();
+        let positions = gs.ecs.read_storage::<Position>();
+        (&names, &positions)
+            .join()
+            .map(|(name, pos)| (name.name.clone(), pos.x, pos.y))
+            .collect()
+    }
+
+    #[test]
+    fn a_seed_always_plays_out_the_same() {
+        let mut first = State::new(Some(42));
+        let mut second = State::new(Some(42));
+        assert_eq!(level_contents(&first), level_contents(&second));
+
+        for _ in 0..50 {
+            first.run_systems();
+            second.run_systems();
+        }
+        assert_eq!(level_contents(&first), level_contents(&second));
+    }
+
+    #[test]
+    fn the_world_behind_the_menu_claims_no_slot() {
+        let mut gs = State::new(Some(42));
+        assert_eq!(gs.save_slot, None);
+        gs.new_game(7, saveload_system::GameMode::Permadeath);
+        assert_eq!(gs.save_slot, None);
+    }
+}
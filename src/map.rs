@@ -1,7 +1,6 @@
 extern crate rltk;
 use super::Rect;
-use rltk::{Algorithm2D, BaseMap, Console, Point, RandomNumberGenerator, Rltk, RGB};
-use std::cmp::{max, min};
+use rltk::{Algorithm2D, BaseMap, Console, Point, Rltk, RGB};
 extern crate specs;
 use serde::{Deserialize, Serialize};
 use specs::prelude::*;
@@ -12,6 +11,7 @@ pub enum TileType {
     Wall,
     Floor,
     DownStairs,
+    UpStairs,
 }
 
 pub const MAPWIDTH: usize = 80;
@@ -29,6 +29,16 @@ pub struct Map {
     pub blocked: Vec<bool>,
     pub depth: i32,
     pub bloodstains: HashSet<usize>,
+    /// Tiles that can't be seen through, such as closed doors. Rebuilt by
+    /// the map indexing system.
+    #[serde(skip_serializing)]
+    #[serde(skip_deserializing)]
+    pub view_blocked: HashSet<usize>,
+    /// Traps that have been found. They are drawn with the map and pathing
+    /// goes around them. Rebuilt by the map indexing system.
+    #[serde(skip_serializing)]
+    #[serde(skip_deserializing)]
+    pub known_traps: HashSet<usize>,
 
     #[serde(skip_serializing)]
     #[serde(skip_deserializing)]
@@ -41,102 +51,80 @@ impl Map {
         (y as usize * self.width as usize) + x as usize
     }
 
-    /// Adds a the room provided to the map
-    fn apply_room_to_map(&mut self, room: &Rect) {
-        for y in room.y1 + 1..=room.y2 {
-            for x in room.x1 + 1..=room.x2 {
-                let idx = self.xy_idx(x, y);
-                self.tiles[idx] = TileType::Floor;
-            }
-        }
-    }
-
-    /// Add a horizontal tunnel between two rooms
-    fn apply_horizontal_tunnel(&mut self, x1: i32, x2: i32, y: i32) {
-        for x in min(x1, x2)..=max(x1, x2) {
-            let idx = self.xy_idx(x, y);
-            if idx > 0 && idx < 80 * 50 {
-                self.tiles[idx as usize] = TileType::Floor;
-            }
-        }
-    }
-
-    /// Add a vertical tunnel between two rooms
-    fn apply_vertical_tunnel(&mut self, y1: i32, y2: i32, x: i32) {
-        for y in min(y1, y2)..=max(y1, y2) {
-            let idx = self.xy_idx(x, y);
-            if idx > 0 && idx < 80 * 50 {
-                self.tiles[idx as usize] = TileType::Floor;
-            }
-        }
-    }
-
-    /// Generate a new map with rooms and corridors
-    pub fn new_map_rooms_and_corridors(new_depth: i32) -> Map {
-        let mut map = Map {
-            tiles: vec![TileType::Wall; MAPWIDTH * MAPHEIGHT],
+    /// Creates a map filled with walls, ready for a builder to carve out
+    pub fn new(new_depth: i32) -> Map {
+        Map {
+            tiles: vec![TileType::Wall; MAPCOUNT],
             rooms: Vec::new(),
             width: MAPWIDTH as i32,
             height: MAPHEIGHT as i32,
-            revealed_tiles: vec![false; MAPWIDTH * MAPHEIGHT],
-            visible_tiles: vec![false; MAPWIDTH * MAPHEIGHT],
-            blocked: vec![false; MAPWIDTH * MAPHEIGHT],
-            tile_content: vec![Vec::new(); MAPWIDTH * MAPHEIGHT],
+            revealed_tiles: vec![false; MAPCOUNT],
+            visible_tiles: vec![false; MAPCOUNT],
+            blocked: vec![false; MAPCOUNT],
+            tile_content: vec![Vec::new(); MAPCOUNT],
             depth: new_depth,
             bloodstains: HashSet::new(),
-        };
-
-        const MAX_ROOMS: i32 = 30;
-        const MIN_SIZE: i32 = 6;
-        const MAX_SIZE: i32 = 10;
+            view_blocked: HashSet::new(),
+            known_traps: HashSet::new(),
+        }
+    }
 
-        let mut rng = RandomNumberGenerator::new();
-        for _ in 0..MAX_ROOMS {
-            let w = rng.range(MIN_SIZE, MAX_SIZE);
-            let h = rng.range(MIN_SIZE, MAX_SIZE);
-            let x = rng.roll_dice(1, map.width - w - 2);
-            let y = rng.roll_dice(1, map.height - h - 2);
+    /// Check if point on map is a valid location to move to
+    fn is_exit_valid(&self, x: i32, y: i32) -> bool {
+        if x < 1 || x > self.width - 1 || y < 1 || y > self.height - 1 {
+            return false;
+        }
+        let idx = self.xy_idx(x, y);
+        !self.blocked[idx] && !self.known_traps.contains(&idx)
+    }
 
-            let new_room = Rect::new(x, y, w, h);
+    /// Neighbours of a tile that pass the given check, with the cost of
+    /// stepping to each
+    fn exits_where(&self, idx: usize, valid: impl Fn(i32, i32) -> bool) -> Vec<(usize, f32)> {
+        let mut exits: Vec<(usize, f32)> = Vec::new();
+        let x = idx as i32 % self.width;
+        let y = idx as i32 / self.width;
+        let w = self.width as usize;
 
-            let mut ok = true;
-            for other_room in map.rooms.iter() {
-                if new_room.intersect(other_room) {
-                    ok = false
-                }
-            }
+        // Cardinal directions
+        if valid(x - 1, y) {
+            exits.push((idx - 1, 1.0))
+        };
+        if valid(x + 1, y) {
+            exits.push((idx + 1, 1.0))
+        };
+        if valid(x, y - 1) {
+            exits.push((idx - w, 1.0))
+        };
+        if valid(x, y + 1) {
+            exits.push((idx + w, 1.0))
+        };
 
-            if ok {
-                map.apply_room_to_map(&new_room);
-                if !map.rooms.is_empty() {
-                    let (new_x, new_y) = new_room.center();
-                    let (prev_x, prev_y) = map.rooms[map.rooms.len() - 1].center();
-                    if rng.range(0, 2) == 1 {
-                        map.apply_horizontal_tunnel(prev_x, new_x, prev_y);
-                        map.apply_vertical_tunnel(prev_y, new_y, new_x);
-                    } else {
-                        map.apply_horizontal_tunnel(prev_x, new_x, new_y);
-                        map.apply_vertical_tunnel(prev_y, new_y, prev_x);
-                    };
-                }
-                map.rooms.push(new_room);
-            }
+        // Diagonals
+        if valid(x - 1, y - 1) {
+            exits.push(((idx - w) - 1, 1.45));
+        }
+        if valid(x + 1, y - 1) {
+            exits.push(((idx - w) + 1, 1.45));
+        }
+        if valid(x - 1, y + 1) {
+            exits.push(((idx + w) - 1, 1.45));
+        }
+        if valid(x + 1, y + 1) {
+            exits.push(((idx + w) + 1, 1.45));
         }
 
-        let stairs_position = map.rooms[map.rooms.len() - 1].center();
-        let stairs_idx = map.xy_idx(stairs_position.0, stairs_position.1);
-        map.tiles[stairs_idx] = TileType::DownStairs;
-
-        map
+        exits
     }
 
-    /// Check if point on map is a valid location to move to
-    fn is_exit_valid(&self, x: i32, y: i32) -> bool {
+    /// Like `is_exit_valid`, but only the terrain counts. Whoever stands on
+    /// the tile doesn't.
+    fn is_terrain_passable(&self, x: i32, y: i32) -> bool {
         if x < 1 || x > self.width - 1 || y < 1 || y > self.height - 1 {
             return false;
         }
         let idx = self.xy_idx(x, y);
-        !self.blocked[idx]
+        self.tiles[idx] != TileType::Wall && !self.known_traps.contains(&idx)
     }
 
     /// Sets walls as blockers
@@ -156,7 +144,7 @@ impl Map {
 
 impl BaseMap for Map {
     fn is_opaque(&self, idx: usize) -> bool {
-        self.tiles[idx] == TileType::Wall
+        self.tiles[idx] == TileType::Wall || self.view_blocked.contains(&idx)
     }
 
     /// Used in a_star path finding algorithm to find the distance from
@@ -171,40 +159,7 @@ impl BaseMap for Map {
     /// Used in a_star path finding algorithm to find the available blocks
     /// around a certain position, for example the location of a monster.
     fn get_available_exits(&self, idx: usize) -> Vec<(usize, f32)> {
-        let mut exits: Vec<(usize, f32)> = Vec::new();
-        let x = idx as i32 % self.width;
-        let y = idx as i32 / self.width;
-        let w = self.width as usize;
-
-        // Cardinal directions
-        if self.is_exit_valid(x - 1, y) {
-            exits.push((idx - 1, 1.0))
-        };
-        if self.is_exit_valid(x + 1, y) {
-            exits.push((idx + 1, 1.0))
-        };
-        if self.is_exit_valid(x, y - 1) {
-            exits.push((idx - w, 1.0))
-        };
-        if self.is_exit_valid(x, y + 1) {
-            exits.push((idx + w, 1.0))
-        };
-
-        // Diagonals
-        if self.is_exit_valid(x - 1, y - 1) {
-            exits.push(((idx - w) - 1, 1.45));
-        }
-        if self.is_exit_valid(x + 1, y - 1) {
-            exits.push(((idx - w) + 1, 1.45));
-        }
-        if self.is_exit_valid(x - 1, y + 1) {
-            exits.push(((idx + w) - 1, 1.45));
-        }
-        if self.is_exit_valid(x + 1, y + 1) {
-            exits.push(((idx + w) + 1, 1.45));
-        }
-
-        exits
+        self.exits_where(idx, |x, y| self.is_exit_valid(x, y))
     }
 }
 
@@ -214,6 +169,50 @@ impl Algorithm2D for Map {
     }
 }
 
+/// The map with only the terrain in the way. Flow fields shared by many
+/// monsters are built over this, so they don't route around the very
+/// monsters following them.
+pub struct TerrainMap<'a> {
+    pub map: &'a Map,
+}
+
+impl BaseMap for TerrainMap<'_> {
+    fn is_opaque(&self, idx: usize) -> bool {
+        self.map.is_opaque(idx)
+    }
+
+    fn get_pathing_distance(&self, idx1: usize, idx2: usize) -> f32 {
+        self.map.get_pathing_distance(idx1, idx2)
+    }
+
+    fn get_available_exits(&self, idx: usize) -> Vec<(usize, f32)> {
+        self.map
+            .exits_where(idx, |x, y| self.map.is_terrain_passable(x, y))
+    }
+}
+
+/// The map as far as the player has seen it. Paths the player picks for
+/// themselves go through this, so they never give away unexplored tiles.
+pub struct RevealedMap<'a> {
+    pub map: &'a Map,
+}
+
+impl BaseMap for RevealedMap<'_> {
+    fn is_opaque(&self, idx: usize) -> bool {
+        self.map.is_opaque(idx)
+    }
+
+    fn get_pathing_distance(&self, idx1: usize, idx2: usize) -> f32 {
+        self.map.get_pathing_distance(idx1, idx2)
+    }
+
+    fn get_available_exits(&self, idx: usize) -> Vec<(usize, f32)> {
+        let mut exits = self.map.get_available_exits(idx);
+        exits.retain(|(exit, _cost)| self.map.revealed_tiles[*exit]);
+        exits
+    }
+}
+
 /// Draws map to the screen. It contains: floors and walls
 pub fn draw_map(ecs: &World, ctx: &mut Rltk) {
     let map = ecs.fetch::<Map>();
@@ -225,7 +224,7 @@ pub fn draw_map(ecs: &World, ctx: &mut Rltk) {
     for (idx, tile) in map.tiles.iter().enumerate() {
         // Paint only the tiles the player has visited to the screen
         if map.revealed_tiles[idx] {
-            let glyph;
+            let mut glyph;
             let mut fg;
             let mut bg = RGB::from_f32(0., 0., 0.);
 
@@ -243,6 +242,14 @@ pub fn draw_map(ecs: &World, ctx: &mut Rltk) {
                     glyph = rltk::to_cp437('>');
                     fg = RGB::from_f32(0., 1.0, 1.0);
                 }
+                TileType::UpStairs => {
+                    glyph = rltk::to_cp437('<');
+                    fg = RGB::from_f32(0., 1.0, 1.0);
+                }
+            }
+            if map.known_traps.contains(&idx) {
+                glyph = rltk::to_cp437('^');
+                fg = RGB::named(rltk::RED);
             }
             if map.bloodstains.contains(&idx) {
                 bg = RGB::from_f32(0.75, 0., 0.);
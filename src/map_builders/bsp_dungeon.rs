@@ -0,0 +1,207 @@
+use super::{
+    common::{apply_room_to_map, central_room, room_door_positions, room_spawn_region},
+    MapBuilder,
+};
+use crate::{map::TileType, Map, Position, Rect};
+use rltk::RandomNumberGenerator;
+
+/// Rooms placed by binary space partitioning: the map is split into ever
+/// smaller rectangles and a room is fitted into some of them, which packs
+/// rooms tightly without overlaps.
+pub struct BspDungeonBuilder {
+    map: Map,
+    starting_position: Position,
+    rects: Vec<Rect>,
+}
+
+impl MapBuilder for BspDungeonBuilder {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator) {
+        self.build(rng);
+    }
+
+    fn get_map(&self) -> Map {
+        self.map.clone()
+    }
+
+    fn get_starting_position(&self) -> Position {
+        self.starting_position.clone()
+    }
+
+    fn get_spawn_regions(&self) -> Vec<Vec<usize>> {
+        self.map
+            .rooms
+            .iter()
+            .skip(1)
+            .map(|room| room_spawn_region(&self.map, room))
+            .collect()
+    }
+
+    fn get_door_positions(&self) -> Vec<usize> {
+        room_door_positions(&self.map)
+    }
+}
+
+impl BspDungeonBuilder {
+    pub fn new(new_depth: i32) -> BspDungeonBuilder {
+        BspDungeonBuilder {
+            map: Map::new(new_depth),
+            starting_position: Position { x: 0, y: 0 },
+            rects: Vec::new(),
+        }
+    }
+
+    fn build(&mut self, rng: &mut RandomNumberGenerator) {
+        const MAX_ATTEMPTS: i32 = 240;
+
+        self.rects.clear();
+        // Leave a border around the whole map
+        self.rects
+            .push(Rect::new(2, 2, self.map.width - 5, self.map.height - 5));
+        let first_room = self.rects[0];
+        self.add_subrects(first_room);
+
+        let mut n_rooms = 0;
+        while n_rooms < MAX_ATTEMPTS {
+            let rect = self.get_random_rect(rng);
+            let candidate = self.get_random_sub_rect(rect, rng);
+
+            if self.is_possible(candidate) {
+                apply_room_to_map(&mut self.map, &candidate);
+                self.map.rooms.push(candidate);
+                self.add_subrects(rect);
+            }
+
+            n_rooms += 1;
+        }
+
+        // Should no room fit, one in the middle keeps the level playable
+        if self.map.rooms.is_empty() {
+            let room = central_room(&self.map);
+            apply_room_to_map(&mut self.map, &room);
+            self.map.rooms.push(room);
+        }
+
+        // Connect the rooms from left to right so corridors don't criss-cross
+        self.map.rooms.sort_by_key(|room| room.x1);
+        for i in 0..self.map.rooms.len() - 1 {
+            let room = self.map.rooms[i];
+            let next_room = self.map.rooms[i + 1];
+            let start_x = room.x1 + 1 + rng.roll_dice(1, i32::abs(room.x1 - room.x2) - 1);
+            let start_y = room.y1 + 1 + rng.roll_dice(1, i32::abs(room.y1 - room.y2) - 1);
+            let end_x =
+                next_room.x1 + 1 + rng.roll_dice(1, i32::abs(next_room.x1 - next_room.x2) - 1);
+            let end_y =
+                next_room.y1 + 1 + rng.roll_dice(1, i32::abs(next_room.y1 - next_room.y2) - 1);
+            self.draw_corridor(start_x, start_y, end_x, end_y);
+        }
+
+        let stairs = self.map.rooms[self.map.rooms.len() - 1].center();
+        let stairs_idx = self.map.xy_idx(stairs.0, stairs.1);
+        self.map.tiles[stairs_idx] = TileType::DownStairs;
+
+        let start = self.map.rooms[0].center();
+        self.starting_position = Position {
+            x: start.0,
+            y: start.1,
+        };
+    }
+
+    /// Splits a rectangle into four quarters that rooms can be placed in
+    fn add_subrects(&mut self, rect: Rect) {
+        let width = i32::abs(rect.x1 - rect.x2);
+        let height = i32::abs(rect.y1 - rect.y2);
+        let half_width = i32::max(width / 2, 1);
+        let half_height = i32::max(height / 2, 1);
+
+        self.rects
+            .push(Rect::new(rect.x1, rect.y1, half_width, half_height));
+        self.rects.push(Rect::new(
+            rect.x1,
+            rect.y1 + half_height,
+            half_width,
+            half_height,
+        ));
+        self.rects.push(Rect::new(
+            rect.x1 + half_width,
+            rect.y1,
+            half_width,
+            half_height,
+        ));
+        self.rects.push(Rect::new(
+            rect.x1 + half_width,
+            rect.y1 + half_height,
+            half_width,
+            half_height,
+        ));
+    }
+
+    fn get_random_rect(&mut self, rng: &mut RandomNumberGenerator) -> Rect {
+        if self.rects.len() == 1 {
+            return self.rects[0];
+        }
+        let idx = (rng.roll_dice(1, self.rects.len() as i32) - 1) as usize;
+        self.rects[idx]
+    }
+
+    /// A room of random size somewhere inside the rectangle
+    fn get_random_sub_rect(&self, rect: Rect, rng: &mut RandomNumberGenerator) -> Rect {
+        let mut result = rect;
+        let rect_width = i32::abs(rect.x1 - rect.x2);
+        let rect_height = i32::abs(rect.y1 - rect.y2);
+
+        let w = i32::max(3, rng.roll_dice(1, i32::min(rect_width, 10)) - 1) + 1;
+        let h = i32::max(3, rng.roll_dice(1, i32::min(rect_height, 10)) - 1) + 1;
+
+        result.x1 += rng.roll_dice(1, 6) - 1;
+        result.y1 += rng.roll_dice(1, 6) - 1;
+        result.x2 = result.x1 + w;
+        result.y2 = result.y1 + h;
+
+        result
+    }
+
+    /// A room fits if it stays on the map and doesn't touch any existing
+    /// floor, keeping a one tile gap around it.
+    fn is_possible(&self, rect: Rect) -> bool {
+        let mut expanded = rect;
+        expanded.x1 -= 2;
+        expanded.x2 += 2;
+        expanded.y1 -= 2;
+        expanded.y2 += 2;
+
+        for y in expanded.y1..=expanded.y2 {
+            for x in expanded.x1..=expanded.x2 {
+                if x > self.map.width - 2 || y > self.map.height - 2 || x < 1 || y < 1 {
+                    return false;
+                }
+                let idx = self.map.xy_idx(x, y);
+                if self.map.tiles[idx] != TileType::Wall {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Digs a corridor that steps diagonally towards the target
+    fn draw_corridor(&mut self, x1: i32, y1: i32, x2: i32, y2: i32) {
+        let mut x = x1;
+        let mut y = y1;
+
+        while x != x2 || y != y2 {
+            if x < x2 {
+                x += 1;
+            } else if x > x2 {
+                x -= 1;
+            } else if y < y2 {
+                y += 1;
+            } else if y > y2 {
+                y -= 1;
+            }
+
+            let idx = self.map.xy_idx(x, y);
+            self.map.tiles[idx] = TileType::Floor;
+        }
+    }
+}
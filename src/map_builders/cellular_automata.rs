@@ -0,0 +1,103 @@
+use super::{
+    common::{
+        central_starting_idx, remove_unreachable_areas_returning_most_distant, square_spawn_regions,
+    },
+    MapBuilder,
+};
+use crate::{map::TileType, Map, Position};
+use rltk::RandomNumberGenerator;
+
+/// Natural looking caves, grown by repeatedly smoothing random noise
+pub struct CellularAutomataBuilder {
+    map: Map,
+    starting_position: Position,
+}
+
+impl MapBuilder for CellularAutomataBuilder {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator) {
+        self.build(rng);
+    }
+
+    fn get_map(&self) -> Map {
+        self.map.clone()
+    }
+
+    fn get_starting_position(&self) -> Position {
+        self.starting_position.clone()
+    }
+
+    fn get_spawn_regions(&self) -> Vec<Vec<usize>> {
+        let start_idx = self
+            .map
+            .xy_idx(self.starting_position.x, self.starting_position.y);
+        square_spawn_regions(&self.map, start_idx)
+    }
+}
+
+impl CellularAutomataBuilder {
+    pub fn new(new_depth: i32) -> CellularAutomataBuilder {
+        CellularAutomataBuilder {
+            map: Map::new(new_depth),
+            starting_position: Position { x: 0, y: 0 },
+        }
+    }
+
+    fn build(&mut self, rng: &mut RandomNumberGenerator) {
+        // Scatter floor over roughly 55% of the map, leaving the edges solid
+        for y in 1..self.map.height - 1 {
+            for x in 1..self.map.width - 1 {
+                let idx = self.map.xy_idx(x, y);
+                if rng.roll_dice(1, 100) > 55 {
+                    self.map.tiles[idx] = TileType::Floor;
+                } else {
+                    self.map.tiles[idx] = TileType::Wall;
+                }
+            }
+        }
+
+        // A tile becomes wall when it is crowded by walls, or when it is
+        // completely isolated
+        for _i in 0..15 {
+            let mut new_tiles = self.map.tiles.clone();
+
+            for y in 1..self.map.height - 1 {
+                for x in 1..self.map.width - 1 {
+                    let idx = self.map.xy_idx(x, y);
+                    let neighbors = self.count_wall_neighbors(x, y);
+                    if neighbors > 4 || neighbors == 0 {
+                        new_tiles[idx] = TileType::Wall;
+                    } else {
+                        new_tiles[idx] = TileType::Floor;
+                    }
+                }
+            }
+
+            self.map.tiles = new_tiles;
+        }
+
+        let start_idx = central_starting_idx(&self.map);
+        self.starting_position = Position {
+            x: start_idx as i32 % self.map.width,
+            y: start_idx as i32 / self.map.width,
+        };
+
+        let exit_idx = remove_unreachable_areas_returning_most_distant(&mut self.map, start_idx);
+        self.map.tiles[exit_idx] = TileType::DownStairs;
+    }
+
+    fn count_wall_neighbors(&self, x: i32, y: i32) -> i32 {
+        let mut neighbors = 0;
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                if dx == 0 && dy == 0 {
+                    continue;
+                }
+                let idx = self.map.xy_idx(x + dx, y + dy);
+                if self.map.tiles[idx] == TileType::Wall {
+                    neighbors += 1;
+                }
+            }
+        }
+        neighbors
+    }
+}
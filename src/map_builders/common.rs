@@ -0,0 +1,178 @@
+use super::Map;
+use crate::{map::TileType, Rect};
+use std::cmp::{max, min};
+use std::collections::HashMap;
+
+/// Side length of the squares open maps are cut into for spawning
+const REGION_SIZE: i32 = 16;
+
+/// Adds a the room provided to the map
+pub fn apply_room_to_map(map: &mut Map, room: &Rect) {
+    for y in room.y1 + 1..=room.y2 {
+        for x in room.x1 + 1..=room.x2 {
+            let idx = map.xy_idx(x, y);
+            map.tiles[idx] = TileType::Floor;
+        }
+    }
+}
+
+/// Add a horizontal tunnel between two rooms
+pub fn apply_horizontal_tunnel(map: &mut Map, x1: i32, x2: i32, y: i32) {
+    for x in min(x1, x2)..=max(x1, x2) {
+        let idx = map.xy_idx(x, y);
+        if idx > 0 && idx < map.tiles.len() {
+            map.tiles[idx] = TileType::Floor;
+        }
+    }
+}
+
+/// Add a vertical tunnel between two rooms
+pub fn apply_vertical_tunnel(map: &mut Map, y1: i32, y2: i32, x: i32) {
+    for y in min(y1, y2)..=max(y1, y2) {
+        let idx = map.xy_idx(x, y);
+        if idx > 0 && idx < map.tiles.len() {
+            map.tiles[idx] = TileType::Floor;
+        }
+    }
+}
+
+/// The floor tiles inside a room, used as its spawn region
+pub fn room_spawn_region(map: &Map, room: &Rect) -> Vec<usize> {
+    let mut region = Vec::new();
+    for y in room.y1 + 1..=room.y2 {
+        for x in room.x1 + 1..=room.x2 {
+            let idx = map.xy_idx(x, y);
+            if map.tiles[idx] == TileType::Floor {
+                region.push(idx);
+            }
+        }
+    }
+    region
+}
+
+/// Spots where a corridor enters a room: floor tiles in the ring of wall
+/// around a room that have wall on both sides, so a door fits snugly.
+/// Openings where a corridor runs along a room's edge are left alone.
+pub fn room_door_positions(map: &Map) -> Vec<usize> {
+    let is_wall = |x: i32, y: i32| map.tiles[map.xy_idx(x, y)] == TileType::Wall;
+    let inside_a_room = |x: i32, y: i32| {
+        map.rooms
+            .iter()
+            .any(|room| x > room.x1 && x <= room.x2 && y > room.y1 && y <= room.y2)
+    };
+
+    let mut doors = Vec::new();
+    for room in map.rooms.iter() {
+        let mut ring = Vec::new();
+        for x in room.x1 + 1..=room.x2 {
+            ring.push((x, room.y1));
+            ring.push((x, room.y2 + 1));
+        }
+        for y in room.y1 + 1..=room.y2 {
+            ring.push((room.x1, y));
+            ring.push((room.x2 + 1, y));
+        }
+
+        for (x, y) in ring {
+            if x < 1 || x >= map.width - 1 || y < 1 || y >= map.height - 1 {
+                continue;
+            }
+            let idx = map.xy_idx(x, y);
+            if map.tiles[idx] != TileType::Floor || inside_a_room(x, y) || doors.contains(&idx) {
+                continue;
+            }
+            let snug = (is_wall(x - 1, y) && is_wall(x + 1, y))
+                || (is_wall(x, y - 1) && is_wall(x, y + 1));
+            if snug {
+                doors.push(idx);
+            }
+        }
+    }
+    doors
+}
+
+/// Turns every floor tile that can't be reached from the start into wall,
+/// and returns the reachable tile furthest away from it.
+pub fn remove_unreachable_areas_returning_most_distant(map: &mut Map, start_idx: usize) -> usize {
+    map.populate_blocked();
+    let map_starts: Vec<usize> = vec![start_idx];
+    let dijkstra_map = rltk::DijkstraMap::new(map.width, map.height, &map_starts, map, 200.0);
+    let mut exit_tile = (start_idx, 0.0f32);
+    for (i, tile) in map.tiles.iter_mut().enumerate() {
+        if *tile == TileType::Floor {
+            let distance_to_start = dijkstra_map.map[i];
+            if distance_to_start == f32::MAX {
+                // We can't get to this tile, so wall it off
+                *tile = TileType::Wall;
+            } else if distance_to_start > exit_tile.1 {
+                exit_tile.0 = i;
+                exit_tile.1 = distance_to_start;
+            }
+        }
+    }
+    map.populate_blocked();
+
+    exit_tile.0
+}
+
+/// Finds the floor tile nearest the middle of the map to start on. A map
+/// with no floor at all just gives the middle.
+pub fn central_starting_idx(map: &Map) -> usize {
+    let (center_x, center_y) = (map.width / 2, map.height / 2);
+    map.tiles
+        .iter()
+        .enumerate()
+        .filter(|(_idx, tile)| **tile == TileType::Floor)
+        .min_by_key(|(idx, _tile)| {
+            let (x, y) = (*idx as i32 % map.width, *idx as i32 / map.width);
+            (x - center_x).pow(2) + (y - center_y).pow(2)
+        })
+        .map_or_else(|| map.xy_idx(center_x, center_y), |(idx, _tile)| idx)
+}
+
+/// A room in the middle of the map, for when a builder couldn't fit any
+pub fn central_room(map: &Map) -> Rect {
+    const SIZE: i32 = 8;
+    Rect::new((map.width - SIZE) / 2, (map.height - SIZE) / 2, SIZE, SIZE)
+}
+
+/// Cuts open maps such as caves into squares, grouping the floor tiles in
+/// each. The square holding the start is left out so the player doesn't
+/// begin next to a monster.
+pub fn square_spawn_regions(map: &Map, start_idx: usize) -> Vec<Vec<usize>> {
+    let start_region = region_key(map, start_idx);
+    let mut regions: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+    for (idx, tile) in map.tiles.iter().enumerate() {
+        let key = region_key(map, idx);
+        if *tile == TileType::Floor && key != start_region {
+            regions.entry(key).or_default().push(idx);
+        }
+    }
+
+    // Keep the order stable so a seed always spawns the same things
+    let mut regions: Vec<((i32, i32), Vec<usize>)> = regions.into_iter().collect();
+    regions.sort_by_key(|region| region.0);
+    regions.into_iter().map(|region| region.1).collect()
+}
+
+fn region_key(map: &Map, idx: usize) -> (i32, i32) {
+    let x = idx as i32 % map.width;
+    let y = idx as i32 / map.width;
+    (x / REGION_SIZE, y / REGION_SIZE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_start_is_the_floor_nearest_the_middle() {
+        let mut map = Map::new(1);
+        let (x, y) = (map.width / 2, map.height / 2);
+        // Floor only to the right of the middle, and further off to the left
+        for idx in [map.xy_idx(x + 3, y), map.xy_idx(x - 10, y)] {
+            map.tiles[idx] = TileType::Floor;
+        }
+        assert_eq!(central_starting_idx(&map), map.xy_idx(x + 3, y));
+    }
+}
@@ -0,0 +1,131 @@
+use super::{
+    common::{remove_unreachable_areas_returning_most_distant, square_spawn_regions},
+    MapBuilder,
+};
+use crate::{map::TileType, Map, Position};
+use rltk::RandomNumberGenerator;
+
+/// Winding tunnels dug by "drunken" diggers stumbling around at random
+/// until enough of the map is open.
+pub struct DrunkardsWalkBuilder {
+    map: Map,
+    starting_position: Position,
+}
+
+impl MapBuilder for DrunkardsWalkBuilder {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator) {
+        self.build(rng);
+    }
+
+    fn get_map(&self) -> Map {
+        self.map.clone()
+    }
+
+    fn get_starting_position(&self) -> Position {
+        self.starting_position.clone()
+    }
+
+    fn get_spawn_regions(&self) -> Vec<Vec<usize>> {
+        let start_idx = self
+            .map
+            .xy_idx(self.starting_position.x, self.starting_position.y);
+        square_spawn_regions(&self.map, start_idx)
+    }
+}
+
+impl DrunkardsWalkBuilder {
+    pub fn new(new_depth: i32) -> DrunkardsWalkBuilder {
+        DrunkardsWalkBuilder {
+            map: Map::new(new_depth),
+            starting_position: Position { x: 0, y: 0 },
+        }
+    }
+
+    fn build(&mut self, rng: &mut RandomNumberGenerator) {
+        const DRUNKARD_LIFETIME: i32 = 400;
+        const FLOOR_PERCENT: f32 = 0.5;
+
+        // Start in the middle of the map
+        self.starting_position = Position {
+            x: self.map.width / 2,
+            y: self.map.height / 2,
+        };
+        let start_idx = self
+            .map
+            .xy_idx(self.starting_position.x, self.starting_position.y);
+        self.map.tiles[start_idx] = TileType::Floor;
+
+        let total_tiles = self.map.width * self.map.height;
+        let desired_floor_tiles = (FLOOR_PERCENT * total_tiles as f32) as usize;
+        let mut floor_tile_count = self.count_floor_tiles();
+        let mut digger_count = 0;
+
+        while floor_tile_count < desired_floor_tiles {
+            // The first digger starts at the player, the rest anywhere
+            // that has already been dug out so everything stays connected
+            let mut drunk_x;
+            let mut drunk_y;
+            if digger_count == 0 {
+                drunk_x = self.starting_position.x;
+                drunk_y = self.starting_position.y;
+            } else {
+                let floor_tiles: Vec<usize> = self
+                    .map
+                    .tiles
+                    .iter()
+                    .enumerate()
+                    .filter(|(_idx, tile)| **tile == TileType::Floor)
+                    .map(|(idx, _tile)| idx)
+                    .collect();
+                let idx = *rng.random_slice_entry(&floor_tiles).unwrap();
+                drunk_x = idx as i32 % self.map.width;
+                drunk_y = idx as i32 / self.map.width;
+            }
+
+            let mut drunk_life = DRUNKARD_LIFETIME;
+            while drunk_life > 0 {
+                let drunk_idx = self.map.xy_idx(drunk_x, drunk_y);
+                self.map.tiles[drunk_idx] = TileType::Floor;
+
+                match rng.roll_dice(1, 4) {
+                    1 => {
+                        if drunk_x > 2 {
+                            drunk_x -= 1;
+                        }
+                    }
+                    2 => {
+                        if drunk_x < self.map.width - 2 {
+                            drunk_x += 1;
+                        }
+                    }
+                    3 => {
+                        if drunk_y > 2 {
+                            drunk_y -= 1;
+                        }
+                    }
+                    _ => {
+                        if drunk_y < self.map.height - 2 {
+                            drunk_y += 1;
+                        }
+                    }
+                }
+
+                drunk_life -= 1;
+            }
+
+            digger_count += 1;
+            floor_tile_count = self.count_floor_tiles();
+        }
+
+        let exit_idx = remove_unreachable_areas_returning_most_distant(&mut self.map, start_idx);
+        self.map.tiles[exit_idx] = TileType::DownStairs;
+    }
+
+    fn count_floor_tiles(&self) -> usize {
+        self.map
+            .tiles
+            .iter()
+            .filter(|tile| **tile == TileType::Floor)
+            .count()
+    }
+}
@@ -0,0 +1,92 @@
+use super::{
+    common::{remove_unreachable_areas_returning_most_distant, square_spawn_regions},
+    MapBuilder,
+};
+use crate::{map::TileType, Map, Position};
+use rltk::RandomNumberGenerator;
+
+/// A perfect maze carved with a recursive backtracker. Every cell sits on
+/// an odd coordinate, with the even ones between them left as walls or
+/// knocked through as passages.
+pub struct MazeBuilder {
+    map: Map,
+    starting_position: Position,
+}
+
+impl MapBuilder for MazeBuilder {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator) {
+        self.build(rng);
+    }
+
+    fn get_map(&self) -> Map {
+        self.map.clone()
+    }
+
+    fn get_starting_position(&self) -> Position {
+        self.starting_position.clone()
+    }
+
+    fn get_spawn_regions(&self) -> Vec<Vec<usize>> {
+        let start_idx = self
+            .map
+            .xy_idx(self.starting_position.x, self.starting_position.y);
+        square_spawn_regions(&self.map, start_idx)
+    }
+}
+
+impl MazeBuilder {
+    pub fn new(new_depth: i32) -> MazeBuilder {
+        MazeBuilder {
+            map: Map::new(new_depth),
+            starting_position: Position { x: 0, y: 0 },
+        }
+    }
+
+    fn build(&mut self, rng: &mut RandomNumberGenerator) {
+        let columns = (self.map.width - 1) / 2;
+        let rows = (self.map.height - 1) / 2;
+        let mut visited = vec![false; (columns * rows) as usize];
+        let cell_idx = |column: i32, row: i32| (row * columns + column) as usize;
+
+        let mut backtrace: Vec<(i32, i32)> = vec![(0, 0)];
+        visited[0] = true;
+        self.carve_cell(0, 0);
+
+        while let Some(&(column, row)) = backtrace.last() {
+            let neighbors: Vec<(i32, i32)> = [(0, -1), (0, 1), (-1, 0), (1, 0)]
+                .iter()
+                .map(|(dx, dy)| (column + dx, row + dy))
+                .filter(|(c, r)| {
+                    *c >= 0 && *c < columns && *r >= 0 && *r < rows && !visited[cell_idx(*c, *r)]
+                })
+                .collect();
+
+            match rng.random_slice_entry(&neighbors) {
+                None => {
+                    // Dead end, step back until there is somewhere new to go
+                    backtrace.pop();
+                }
+                Some(&(next_column, next_row)) => {
+                    visited[cell_idx(next_column, next_row)] = true;
+                    self.carve_cell(next_column, next_row);
+                    // Knock through the wall between the two cells
+                    let wall_x = column + next_column + 1;
+                    let wall_y = row + next_row + 1;
+                    let wall_idx = self.map.xy_idx(wall_x, wall_y);
+                    self.map.tiles[wall_idx] = TileType::Floor;
+                    backtrace.push((next_column, next_row));
+                }
+            }
+        }
+
+        self.starting_position = Position { x: 1, y: 1 };
+        let start_idx = self.map.xy_idx(1, 1);
+        let exit_idx = remove_unreachable_areas_returning_most_distant(&mut self.map, start_idx);
+        self.map.tiles[exit_idx] = TileType::DownStairs;
+    }
+
+    fn carve_cell(&mut self, column: i32, row: i32) {
+        let idx = self.map.xy_idx(column * 2 + 1, row * 2 + 1);
+        self.map.tiles[idx] = TileType::Floor;
+    }
+}
@@ -0,0 +1,45 @@
+use super::{Map, Position};
+use rltk::RandomNumberGenerator;
+
+mod bsp_dungeon;
+mod cellular_automata;
+mod common;
+mod drunkard;
+mod maze;
+mod simple_map;
+use bsp_dungeon::BspDungeonBuilder;
+use cellular_automata::CellularAutomataBuilder;
+use drunkard::DrunkardsWalkBuilder;
+use maze::MazeBuilder;
+use simple_map::SimpleMapBuilder;
+
+/// A map generation algorithm. Builders carve out a map, decide where the
+/// player starts and hand back the areas that should be filled with monsters
+/// and items.
+pub trait MapBuilder {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator);
+    fn get_map(&self) -> Map;
+    fn get_starting_position(&self) -> Position;
+    /// Groups of tile indexes, each of which gets its own spawn roll
+    fn get_spawn_regions(&self) -> Vec<Vec<usize>>;
+    /// Tile indexes that get a door. Layouts without rooms have none.
+    fn get_door_positions(&self) -> Vec<usize> {
+        Vec::new()
+    }
+}
+
+/// Picks the generation algorithm for a level. The first level is always
+/// rooms and corridors so a new game starts somewhere familiar.
+pub fn random_builder(new_depth: i32, rng: &mut RandomNumberGenerator) -> Box<dyn MapBuilder> {
+    if new_depth == 1 {
+        return Box::new(SimpleMapBuilder::new(new_depth));
+    }
+
+    match rng.roll_dice(1, 5) {
+        1 => Box::new(BspDungeonBuilder::new(new_depth)),
+        2 => Box::new(CellularAutomataBuilder::new(new_depth)),
+        3 => Box::new(DrunkardsWalkBuilder::new(new_depth)),
+        4 => Box::new(MazeBuilder::new(new_depth)),
+        _ => Box::new(SimpleMapBuilder::new(new_depth)),
+    }
+}
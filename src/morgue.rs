@@ -0,0 +1,168 @@
+use super::{
+    gamelog::GameLog, gui, identification::IdentificationTable, map::Map, saveload_system,
+    seed::WorldSeed, Ammunition, Equipped, Experience, InBackpack, Name,
+};
+use serde::{Deserialize, Serialize};
+use specs::prelude::*;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// How many of the final log entries a recap keeps
+const RECAP_LOG_LINES: usize = 20;
+
+/// Running tally of the things only worth knowing once the run is over
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct RunStats {
+    /// Monsters the player has killed, by name
+    pub kills: BTreeMap<String, i32>,
+    /// What dealt the player their last hit
+    pub killed_by: Option<String>,
+    pub deepest: i32,
+}
+
+/// Everything about a finished run, for the morgue file and the game over
+/// screen
+pub struct DeathRecap {
+    pub killed_by: String,
+    pub deepest: i32,
+    pub turns: i32,
+    pub level: i32,
+    pub seed: u64,
+    pub mode: saveload_system::GameMode,
+    pub kills: Vec<(String, i32)>,
+    pub equipment: Vec<String>,
+    pub inventory: Vec<String>,
+    pub last_messages: Vec<String>,
+    /// Where the morgue file ended up, once written
+    pub morgue_file: Option<PathBuf>,
+}
+
+impl DeathRecap {
+    pub fn new(ecs: &World) -> DeathRecap {
+        let player_entity = *ecs.fetch::<Entity>();
+        let run_stats = ecs.fetch::<RunStats>();
+        let log = ecs.fetch::<GameLog>();
+        let names = ecs.read_storage::<Name>();
+        let ammunition = ecs.read_storage::<Ammunition>();
+        let identification = ecs.fetch::<IdentificationTable>();
+        let entities = ecs.entities();
+
+        let equipped = ecs.read_storage::<Equipped>();
+        let equipment = (&entities, &equipped, &names)
+            .join()
+            .filter(|(_entity, equipped, _name)| equipped.owner == player_entity)
+            .map(|(entity, _equipped, name)| {
+                gui::item_label(name, ammunition.get(entity), &identification)
+            })
+            .collect();
+        let backpack = ecs.read_storage::<InBackpack>();
+        let inventory = (&entities, &backpack, &names)
+            .join()
+            .filter(|(_entity, pack, _name)| pack.owner == player_entity)
+            .map(|(entity, _pack, name)| {
+                gui::item_label(name, ammunition.get(entity), &identification)
+            })
+            .collect();
+
+        let skipped = log.entries.len().saturating_sub(RECAP_LOG_LINES);
+        let last_messages = log.entries[skipped..]
+            .iter()
+            .map(|entry| format!("[{:>5}] {}", entry.turn, entry.text))
+            .collect();
+
+        DeathRecap {
+            killed_by: run_stats
+                .killed_by
+                .clone()
+                .unwrap_or_else(|| "something unknown".to_string()),
+            deepest: i32::max(run_stats.deepest, ecs.fetch::<Map>().depth),
+            turns: log.turn,
+            level: ecs
+                .read_storage::<Experience>()
+                .get(player_entity)
+                .map_or(1, |experience| experience.level),
+            seed: ecs.fetch::<WorldSeed>().seed,
+            mode: *ecs.fetch::<saveload_system::GameMode>(),
+            kills: run_stats
+                .kills
+                .iter()
+                .map(|(name, count)| (name.clone(), *count))
+                .collect(),
+            equipment,
+            inventory,
+            last_messages,
+            morgue_file: None,
+        }
+    }
+
+    /// One line on how the run ended
+    pub fn epitaph(&self) -> String {
+        format!(
+            "Killed by {} on turn {}, having reached depth {}.",
+            self.killed_by, self.turns, self.deepest
+        )
+    }
+
+    pub fn kill_summary(&self) -> String {
+        if self.kills.is_empty() {
+            return "nothing".to_string();
+        }
+        self.kills
+            .iter()
+            .map(|(name, count)| format!("{} x{}", name, count))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    fn to_text(&self) -> String {
+        let mode = match self.mode {
+            saveload_system::GameMode::Casual => "casual",
+            saveload_system::GameMode::Permadeath => "permadeath",
+        };
+        let mut text = format!(
+            "Roguelike morgue file, written {}\nSeed {}, {} mode\n\n{}\nCharacter level {}\n",
+            chrono::Local::now().format("%Y-%m-%d %H:%M"),
+            self.seed,
+            mode,
+            self.epitaph(),
+            self.level
+        );
+
+        text.push_str("\nKills:\n");
+        for (name, count) in self.kills.iter() {
+            text.push_str(&format!("  {:<24}{:>4}\n", name, count));
+        }
+        let total: i32 = self.kills.iter().map(|(_name, count)| count).sum();
+        text.push_str(&format!("  {:<24}{:>4}\n", "Total", total));
+
+        let sections = [
+            ("Equipment", &self.equipment),
+            ("Inventory", &self.inventory),
+            ("Last messages", &self.last_messages),
+        ];
+        for (title, lines) in sections.iter() {
+            text.push_str(&format!("\n{}:\n", title));
+            if lines.is_empty() {
+                text.push_str("  (none)\n");
+            }
+            for line in lines.iter() {
+                text.push_str(&format!("  {}\n", line));
+            }
+        }
+        text
+    }
+}
+
+/// Writes the recap out as a text file named after the time of death
+pub fn write_morgue(recap: &DeathRecap) -> io::Result<PathBuf> {
+    let dir = saveload_system::game_data_dir().join("morgue");
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(format!(
+        "morgue-{}.txt",
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    fs::write(&path, recap.to_text())?;
+    Ok(path)
+}
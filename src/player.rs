@@ -1,15 +1,52 @@
 extern crate rltk;
-use rltk::{Point, Rltk, VirtualKeyCode};
+use rltk::{Point, RandomNumberGenerator, Rltk, VirtualKeyCode};
 extern crate specs;
 use super::{
-    gamelog::GameLog, map, CombatStats, Item, Monster, Player, Position, RunState, State, Viewshed,
-    WantsToMelee, WantsToPickupItem,
+    flow_field::FlowField, gamelog::GameLog, gui, map, ranged_combat_system, visibility_system,
+    Ammunition, CombatStats, Door, EntityMoved, Equipped, Hidden, HungerClock, HungerState,
+    InBackpack, Item, Monster, Name, Player, Position, RangedWeapon, Renderable, RunState, State,
+    StatusEffectType, StatusEffects, Viewshed, WantsToMelee, WantsToPickupItem,
 };
 use specs::prelude::*;
 use std::cmp::{max, min};
 
+/// Tiles the player is walking to on their own, one step per turn
+#[derive(Default)]
+pub struct TravelPath {
+    pub steps: Vec<usize>,
+}
+
+/// Auto-explore in progress. Remembers the player's health as of the last
+/// step, so getting hurt in between can stop it.
+#[derive(Default)]
+pub struct AutoExplore {
+    pub active: bool,
+    pub pick_up_items: bool,
+    last_hp: i32,
+}
+
 /// Controls player keyboard input
 pub fn player_input(gs: &mut State, ctx: &mut Rltk) -> RunState {
+    // A paralyzed player can only wait for it to wear off
+    if player_has_status(&gs.ecs, StatusEffectType::Paralysis) {
+        return RunState::PlayerTurn;
+    }
+
+    // Any key or click takes back control from travel and exploring
+    if ctx.key.is_some() || ctx.left_click {
+        gs.ecs.write_resource::<TravelPath>().steps.clear();
+        gs.ecs.write_resource::<AutoExplore>().active = false;
+    } else if let Some(runstate) = travel_step(&mut gs.ecs) {
+        return runstate;
+    } else if let Some(runstate) = explore_step(&mut gs.ecs) {
+        return runstate;
+    }
+
+    if ctx.left_click {
+        let (x, y) = ctx.mouse_pos();
+        return start_travel(&gs.ecs, x, y);
+    }
+
     // Player movement
     match ctx.key {
         None => return RunState::AwaitingInput, // Nothing happened
@@ -42,6 +79,15 @@ pub fn player_input(gs: &mut State, ctx: &mut Rltk) -> RunState {
             VirtualKeyCode::I => return RunState::ShowInventory,
             VirtualKeyCode::D => return RunState::ShowDropItem,
             VirtualKeyCode::R => return RunState::ShowUnequip,
+            VirtualKeyCode::F => return fire_weapon(&mut gs.ecs),
+            VirtualKeyCode::C => return close_door(&mut gs.ecs),
+            VirtualKeyCode::X => return start_explore(&mut gs.ecs, ctx.shift),
+            VirtualKeyCode::M => {
+                return RunState::ShowLog {
+                    scroll: 0,
+                    filter: gui::LogFilter::default(),
+                }
+            }
 
             // Save and Quit
             VirtualKeyCode::Escape => return RunState::SaveGame,
@@ -50,6 +96,11 @@ pub fn player_input(gs: &mut State, ctx: &mut Rltk) -> RunState {
                     return RunState::NextLevel;
                 }
             }
+            VirtualKeyCode::Comma => {
+                if try_previous_level(&mut gs.ecs) {
+                    return RunState::PreviousLevel;
+                }
+            }
 
             // If keys don't match any of the above then don't tick game
             _ => return RunState::AwaitingInput,
@@ -59,15 +110,283 @@ pub fn player_input(gs: &mut State, ctx: &mut Rltk) -> RunState {
     RunState::PlayerTurn
 }
 
-pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
+/// Plans a walk to a tile the player has seen, avoiding what they know
+/// blocks the way
+fn start_travel(ecs: &World, x: i32, y: i32) -> RunState {
+    let map = ecs.fetch::<map::Map>();
+    if x < 0 || x >= map.width || y < 0 || y >= map.height {
+        return RunState::AwaitingInput;
+    }
+    let destination = map.xy_idx(x, y);
+    if !map.revealed_tiles[destination] || map.tiles[destination] == map::TileType::Wall {
+        return RunState::AwaitingInput;
+    }
+
+    let player_pos = ecs.fetch::<Point>();
+    let start = map.xy_idx(player_pos.x, player_pos.y);
+    if start == destination {
+        return RunState::AwaitingInput;
+    }
+    let path = rltk::a_star_search(start, destination, &map::RevealedMap { map: &map });
+    let mut gamelog = ecs.fetch_mut::<GameLog>();
+    if !path.success {
+        gamelog.system("You don't know a way there.".to_string());
+        return RunState::AwaitingInput;
+    }
+    if monster_in_view(ecs) {
+        gamelog.system("You can't travel with monsters in view.".to_string());
+        return RunState::AwaitingInput;
+    }
+
+    // The first step is where the player already stands
+    ecs.fetch_mut::<TravelPath>().steps = path.steps.into_iter().skip(1).rev().collect();
+    RunState::AwaitingInput
+}
+
+/// Takes the next step of a planned walk. Stops, and hands control back,
+/// once a monster shows up or the way turns out to be blocked.
+fn travel_step(ecs: &mut World) -> Option<RunState> {
+    let next = *ecs.fetch::<TravelPath>().steps.last()?;
+    if monster_in_view(ecs) {
+        ecs.fetch_mut::<TravelPath>().steps.clear();
+        ecs.fetch_mut::<GameLog>()
+            .system("You stop, a monster comes into view.".to_string());
+        return Some(RunState::AwaitingInput);
+    }
+
+    let step = {
+        let map = ecs.fetch::<map::Map>();
+        let player_pos = ecs.fetch::<Point>();
+        let delta_x = next as i32 % map.width - player_pos.x;
+        let delta_y = next as i32 / map.width - player_pos.y;
+        if delta_x.abs() > 1 || delta_y.abs() > 1 {
+            Err("You stop, having lost your way.")
+        } else if map.blocked[next] {
+            Err("You stop, something is in the way.")
+        } else {
+            Ok((delta_x, delta_y))
+        }
+    };
+    match step {
+        Ok((delta_x, delta_y)) => {
+            try_move_player(delta_x, delta_y, ecs);
+            // Opening a door takes the turn without moving, so the step is
+            // only done once the player stands on it
+            let arrived = {
+                let map = ecs.fetch::<map::Map>();
+                let player_pos = ecs.fetch::<Point>();
+                map.xy_idx(player_pos.x, player_pos.y) == next
+            };
+            if arrived {
+                ecs.fetch_mut::<TravelPath>().steps.pop();
+            }
+            Some(RunState::PlayerTurn)
+        }
+        Err(reason) => {
+            ecs.fetch_mut::<TravelPath>().steps.clear();
+            ecs.fetch_mut::<GameLog>().system(reason.to_string());
+            Some(RunState::AwaitingInput)
+        }
+    }
+}
+
+/// Sets off exploring the level, optionally picking up items on the way
+fn start_explore(ecs: &mut World, pick_up_items: bool) -> RunState {
+    if monster_in_view(ecs) {
+        ecs.fetch_mut::<GameLog>()
+            .system("You can't explore with monsters in view.".to_string());
+        return RunState::AwaitingInput;
+    }
+    let hp = player_hp(ecs);
+    let mut explore = ecs.fetch_mut::<AutoExplore>();
+    explore.active = true;
+    explore.pick_up_items = pick_up_items;
+    explore.last_hp = hp;
+    RunState::AwaitingInput
+}
+
+fn stop_exploring(ecs: &mut World, reason: &str) -> Option<RunState> {
+    ecs.fetch_mut::<AutoExplore>().active = false;
+    ecs.fetch_mut::<GameLog>().system(reason.to_string());
+    Some(RunState::AwaitingInput)
+}
+
+/// Takes one step of auto-explore, toward the nearest tile at the edge of
+/// what the player has seen. Once there is nothing left to see it heads for
+/// the stairs down.
+fn explore_step(ecs: &mut World) -> Option<RunState> {
+    let (active, pick_up_items, last_hp) = {
+        let explore = ecs.fetch::<AutoExplore>();
+        (explore.active, explore.pick_up_items, explore.last_hp)
+    };
+    if !active {
+        return None;
+    }
+
+    let hp = player_hp(ecs);
+    if hp < last_hp {
+        return stop_exploring(ecs, "You stop exploring, something hurt you.");
+    }
+    ecs.fetch_mut::<AutoExplore>().last_hp = hp;
+    if monster_in_view(ecs) {
+        return stop_exploring(ecs, "You stop exploring, a monster comes into view.");
+    }
+
+    let player_pos = *ecs.fetch::<Point>();
+    let item_tiles: Vec<usize> = if pick_up_items {
+        let map = ecs.fetch::<map::Map>();
+        let items = ecs.read_storage::<Item>();
+        let positions = ecs.read_storage::<Position>();
+        let hidden = ecs.read_storage::<Hidden>();
+        (&items, &positions, !&hidden)
+            .join()
+            .map(|(_item, pos, _hidden)| map.xy_idx(pos.x, pos.y))
+            .filter(|idx| map.revealed_tiles[*idx])
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let step = {
+        let map = ecs.fetch::<map::Map>();
+        let player_idx = map.xy_idx(player_pos.x, player_pos.y);
+        let mut targets = unexplored_edge(&map);
+        targets.extend(item_tiles.iter());
+        if item_tiles.contains(&player_idx) {
+            ExploreStep::PickUp
+        } else if targets.is_empty() && map.tiles[player_idx] == map::TileType::DownStairs {
+            ExploreStep::Done("The level is explored, and you reach the stairs.")
+        } else {
+            // With nothing left to see, make for the stairs down instead
+            if targets.is_empty() {
+                targets = (0..map.tiles.len())
+                    .filter(|idx| {
+                        map.revealed_tiles[*idx] && map.tiles[*idx] == map::TileType::DownStairs
+                    })
+                    .collect();
+            }
+            let known = map::RevealedMap { map: &map };
+            let flow = FlowField::toward(map.tiles.len(), &targets, &known);
+            match flow.next_step(player_idx, &known) {
+                Some(next) => ExploreStep::Move(
+                    next as i32 % map.width - player_pos.x,
+                    next as i32 / map.width - player_pos.y,
+                ),
+                _ => ExploreStep::Done("There is nothing left to explore."),
+            }
+        }
+    };
+
+    match step {
+        ExploreStep::PickUp => {
+            get_item(ecs);
+            Some(RunState::PlayerTurn)
+        }
+        ExploreStep::Move(delta_x, delta_y) => {
+            try_move_player(delta_x, delta_y, ecs);
+            Some(RunState::PlayerTurn)
+        }
+        ExploreStep::Done(reason) => stop_exploring(ecs, reason),
+    }
+}
+
+enum ExploreStep {
+    PickUp,
+    Move(i32, i32),
+    Done(&'static str),
+}
+
+/// Tiles the player has seen and could stand on, that border on tiles they
+/// haven't seen yet. The outer edge of the map is always wall, so it
+/// doesn't count.
+fn unexplored_edge(map: &map::Map) -> Vec<usize> {
+    let mut edge = Vec::new();
+    for y in 1..map.height - 1 {
+        for x in 1..map.width - 1 {
+            let idx = map.xy_idx(x, y);
+            if !map.revealed_tiles[idx] || map.tiles[idx] == map::TileType::Wall {
+                continue;
+            }
+            let borders_unseen = (-1..=1).any(|dy| {
+                (-1..=1).any(|dx| {
+                    let (nx, ny) = (x + dx, y + dy);
+                    nx > 0
+                        && nx < map.width - 1
+                        && ny > 0
+                        && ny < map.height - 1
+                        && !map.revealed_tiles[map.xy_idx(nx, ny)]
+                })
+            });
+            if borders_unseen {
+                edge.push(idx);
+            }
+        }
+    }
+    edge
+}
+
+fn player_hp(ecs: &World) -> i32 {
+    let player_entity = ecs.fetch::<Entity>();
+    let combat_stats = ecs.read_storage::<CombatStats>();
+    combat_stats.get(*player_entity).map_or(0, |stats| stats.hp)
+}
+
+/// Whether the player can see any monster
+fn monster_in_view(ecs: &World) -> bool {
+    let player_entity = ecs.fetch::<Entity>();
+    let viewsheds = ecs.read_storage::<Viewshed>();
+    let monsters = ecs.read_storage::<Monster>();
+    let hidden = ecs.read_storage::<Hidden>();
+    let map = ecs.fetch::<map::Map>();
+    viewsheds.get(*player_entity).is_some_and(|viewshed| {
+        viewshed.visible_tiles.iter().any(|tile| {
+            let idx = map.xy_idx(tile.x, tile.y);
+            map.tile_content[idx]
+                .iter()
+                .any(|e| monsters.get(*e).is_some() && hidden.get(*e).is_none())
+        })
+    })
+}
+
+pub fn player_has_status(ecs: &World, kind: StatusEffectType) -> bool {
+    let player_entity = ecs.fetch::<Entity>();
+    let status_effects = ecs.read_storage::<StatusEffects>();
+    status_effects
+        .get(*player_entity)
+        .is_some_and(|s| s.has(kind))
+}
+
+pub fn try_move_player(mut delta_x: i32, mut delta_y: i32, ecs: &mut World) {
+    // Confused players stumble off in a random direction
+    if player_has_status(ecs, StatusEffectType::Confusion) {
+        const DIRECTIONS: [(i32, i32); 8] = [
+            (-1, -1),
+            (0, -1),
+            (1, -1),
+            (-1, 0),
+            (1, 0),
+            (-1, 1),
+            (0, 1),
+            (1, 1),
+        ];
+        let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+        let direction = DIRECTIONS[rng.range(0, 8) as usize];
+        delta_x = direction.0;
+        delta_y = direction.1;
+    }
+
     let mut positions = ecs.write_storage::<Position>();
     let mut players = ecs.write_storage::<Player>();
     let mut viewsheds = ecs.write_storage::<Viewshed>();
     let combat_stats = ecs.read_storage::<CombatStats>();
     let mut wants_to_melee = ecs.write_storage::<WantsToMelee>();
+    let mut doors = ecs.write_storage::<Door>();
+    let mut renderables = ecs.write_storage::<Renderable>();
+    let mut entity_moved = ecs.write_storage::<EntityMoved>();
     let entities = ecs.entities();
     let map = ecs.fetch::<map::Map>();
 
+    let mut opened_door = false;
     for (_player, pos, viewshed, entity) in
         (&mut players, &mut positions, &mut viewsheds, &entities).join()
     {
@@ -90,6 +409,21 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
                     .expect("Add target failed");
                 return; // So we don't move after attacking
             }
+
+            // Walking into a closed door opens it
+            if let Some(door) = doors.get_mut(*potential_target) {
+                if !door.open {
+                    door.open = true;
+                    if let Some(renderable) = renderables.get_mut(*potential_target) {
+                        renderable.glyph = door.glyph();
+                    }
+                    opened_door = true;
+                    break;
+                }
+            }
+        }
+        if opened_door {
+            break;
         }
 
         // If there is nothing blocking the player movement in the destination tile
@@ -100,6 +434,9 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
             pos.y = min(map.height - 1, max(0, pos.y + delta_y));
             // Mark viewshed as dirty so that it has to be recalculated.
             viewshed.dirty = true;
+            entity_moved
+                .insert(entity, EntityMoved {})
+                .expect("Unable to insert marker");
 
             // Save new player position also to GameState
             let mut ppos = ecs.write_resource::<Point>();
@@ -107,6 +444,9 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
             ppos.y = pos.y;
         }
     }
+    if opened_door {
+        visibility_system::door_changed(&mut viewsheds);
+    }
 }
 
 pub fn try_next_level(ecs: &mut World) -> bool {
@@ -117,9 +457,20 @@ pub fn try_next_level(ecs: &mut World) -> bool {
         true
     } else {
         let mut gamelog = ecs.fetch_mut::<GameLog>();
-        gamelog
-            .entries
-            .push("There is no way down from here.".to_string());
+        gamelog.system("There is no way down from here.".to_string());
+        false
+    }
+}
+
+pub fn try_previous_level(ecs: &mut World) -> bool {
+    let player_pos = ecs.fetch::<Point>();
+    let map = ecs.fetch::<map::Map>();
+    let player_idx = map.xy_idx(player_pos.x, player_pos.y);
+    if map.tiles[player_idx] == map::TileType::UpStairs {
+        true
+    } else {
+        let mut gamelog = ecs.fetch_mut::<GameLog>();
+        gamelog.system("There is no way up from here.".to_string());
         false
     }
 }
@@ -140,9 +491,7 @@ fn get_item(ecs: &mut World) {
     }
 
     match target_item {
-        None => gamelog
-            .entries
-            .push("There is nothing here to pick up.".to_string()),
+        None => gamelog.system("There is nothing here to pick up.".to_string()),
         Some(item) => {
             let mut pickup = ecs.write_storage::<WantsToPickupItem>();
             pickup
@@ -158,6 +507,80 @@ fn get_item(ecs: &mut World) {
     }
 }
 
+/// Closes an open door next to the player, as long as nothing is standing
+/// in the doorway
+fn close_door(ecs: &mut World) -> RunState {
+    let player_pos = ecs.fetch::<Point>();
+    let map = ecs.fetch::<map::Map>();
+    let mut doors = ecs.write_storage::<Door>();
+    let mut renderables = ecs.write_storage::<Renderable>();
+    let mut viewsheds = ecs.write_storage::<Viewshed>();
+    let mut gamelog = ecs.fetch_mut::<GameLog>();
+
+    for y in player_pos.y - 1..=player_pos.y + 1 {
+        for x in player_pos.x - 1..=player_pos.x + 1 {
+            let idx = map.xy_idx(x, y);
+            let contents = &map.tile_content[idx];
+            let door_entity = contents
+                .iter()
+                .find(|e| doors.get(**e).is_some_and(|door| door.open));
+            if let Some(door_entity) = door_entity {
+                if contents.len() > 1 {
+                    gamelog.system("Something is in the way of the door.".to_string());
+                    return RunState::AwaitingInput;
+                }
+                let door = doors.get_mut(*door_entity).unwrap();
+                door.open = false;
+                if let Some(renderable) = renderables.get_mut(*door_entity) {
+                    renderable.glyph = door.glyph();
+                }
+                visibility_system::door_changed(&mut viewsheds);
+                return RunState::PlayerTurn;
+            }
+        }
+    }
+
+    gamelog.system("There is no open door next to you.".to_string());
+    RunState::AwaitingInput
+}
+
+/// Starts aiming the equipped launcher, if there is one
+fn fire_weapon(ecs: &mut World) -> RunState {
+    let player_entity = ecs.fetch::<Entity>();
+    let entities = ecs.entities();
+    let ranged_weapons = ecs.read_storage::<RangedWeapon>();
+    let equipped = ecs.read_storage::<Equipped>();
+    let backpack = ecs.read_storage::<InBackpack>();
+    let names = ecs.read_storage::<Name>();
+    let ammunition = ecs.read_storage::<Ammunition>();
+
+    match ranged_combat_system::ranged_weapon(*player_entity, &entities, &ranged_weapons, &equipped)
+    {
+        Some(weapon) => {
+            if ranged_combat_system::has_ammo(
+                *player_entity,
+                &weapon,
+                &backpack,
+                &names,
+                &ammunition,
+            ) {
+                RunState::ShowFiring {
+                    range: weapon.range,
+                }
+            } else {
+                let mut gamelog = ecs.fetch_mut::<GameLog>();
+                gamelog.system(format!("You have no {} left.", weapon.ammo.unwrap()));
+                RunState::AwaitingInput
+            }
+        }
+        None => {
+            let mut gamelog = ecs.fetch_mut::<GameLog>();
+            gamelog.system("You have nothing to fire.".to_string());
+            RunState::AwaitingInput
+        }
+    }
+}
+
 fn skip_turn(ecs: &mut World) -> RunState {
     let player_entity = ecs.fetch::<Entity>();
     let viewshed_components = ecs.read_storage::<Viewshed>();
@@ -180,6 +603,34 @@ fn skip_turn(ecs: &mut World) -> RunState {
         }
     }
 
+    // Resting on an empty stomach doesn't restore anything
+    let hunger_clocks = ecs.read_storage::<HungerClock>();
+    if let Some(hunger) = hunger_clocks.get(*player_entity) {
+        match hunger.state {
+            HungerState::Hungry | HungerState::Starving => can_heal = false,
+            _ => {}
+        }
+    }
+
+    // Resting is also a chance to look around for traps
+    {
+        let mut hidden = ecs.write_storage::<Hidden>();
+        let names = ecs.read_storage::<Name>();
+        let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+        let mut gamelog = ecs.fetch_mut::<GameLog>();
+        for tile in viewshed.visible_tiles.iter() {
+            let idx = worldmap_resource.xy_idx(tile.x, tile.y);
+            for entity_id in worldmap_resource.tile_content[idx].iter() {
+                if hidden.get(*entity_id).is_some() && rng.roll_dice(1, 3) == 1 {
+                    hidden.remove(*entity_id);
+                    if let Some(name) = names.get(*entity_id) {
+                        gamelog.system(format!("You spot a {}.", &name.name));
+                    }
+                }
+            }
+        }
+    }
+
     if can_heal {
         let mut health_components = ecs.write_storage::<CombatStats>();
         let player_hp = health_components.get_mut(*player_entity).unwrap();
@@ -0,0 +1,152 @@
+use crate::{AiProfile, EquipmentSlot, StatusEffect};
+use serde::Deserialize;
+
+/// Top level of a raw file
+#[derive(Deserialize, Debug, Clone)]
+pub struct Raws {
+    pub entities: Vec<RawEntity>,
+}
+
+/// One spawnable thing, identified by its name
+#[derive(Deserialize, Debug, Clone)]
+pub struct RawEntity {
+    pub name: String,
+    pub spawn: Option<RawSpawn>,
+    /// Items that go by a made up name until the player identifies them
+    pub unidentified: Option<UnidentifiedKind>,
+    pub components: RawComponents,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub enum UnidentifiedKind {
+    Scroll,
+    Potion,
+}
+
+/// How often an entity shows up and on which depths. Entities without
+/// spawn information can still be created by name.
+#[derive(Deserialize, Debug, Clone)]
+pub struct RawSpawn {
+    pub weight: i32,
+    pub min_depth: i32,
+    pub max_depth: i32,
+    #[serde(default)]
+    pub add_map_depth_to_weight: bool,
+}
+
+/// Every component an entity can be given. Components that are left out
+/// of the raw file aren't added.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct RawComponents {
+    pub renderable: Option<RawRenderable>,
+    pub monster: Option<RawFlag>,
+    pub ai: Option<AiProfile>,
+    pub blocks_tile: Option<RawFlag>,
+    pub combat_stats: Option<RawCombatStats>,
+    pub viewshed: Option<RawViewshed>,
+    pub energy: Option<RawEnergy>,
+    pub item: Option<RawFlag>,
+    pub consumable: Option<RawFlag>,
+    pub provides_healing: Option<RawProvidesHealing>,
+    pub provides_food: Option<RawFlag>,
+    pub magic_mapper: Option<RawFlag>,
+    pub provides_clairvoyance: Option<RawProvidesClairvoyance>,
+    pub ranged: Option<RawRanged>,
+    pub inflicts_damage: Option<RawInflictsDamage>,
+    pub area_of_effect: Option<RawAreaOfEffect>,
+    pub applies_status: Option<Vec<StatusEffect>>,
+    pub equippable: Option<RawEquippable>,
+    pub melee_weapon: Option<RawMeleeWeapon>,
+    pub defense_bonus: Option<RawDefenseBonus>,
+    pub ranged_weapon: Option<RawRangedWeapon>,
+    pub ammunition: Option<RawAmmunition>,
+    pub hidden: Option<RawFlag>,
+    pub entry_trigger: Option<RawFlag>,
+    pub single_activation: Option<RawFlag>,
+}
+
+/// Components that carry no data, written as `{}`
+#[derive(Deserialize, Debug, Clone)]
+pub struct RawFlag {}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RawRenderable {
+    pub glyph: String,
+    pub fg: String,
+    pub bg: String,
+    pub order: i32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RawCombatStats {
+    pub max_hp: i32,
+    pub defense: i32,
+    pub power: i32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RawViewshed {
+    pub range: i32,
+}
+
+/// Speed relative to the player's 100. Monsters move at normal speed
+/// unless given one.
+#[derive(Deserialize, Debug, Clone)]
+pub struct RawEnergy {
+    pub speed: i32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RawProvidesHealing {
+    pub heal_amount: i32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RawProvidesClairvoyance {
+    pub turns: i32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RawRanged {
+    pub range: i32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RawInflictsDamage {
+    pub damage: i32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RawAreaOfEffect {
+    pub radius: i32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RawEquippable {
+    pub slot: EquipmentSlot,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RawMeleeWeapon {
+    /// Dice expression such as `1d6+1`
+    pub damage: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RawDefenseBonus {
+    pub defense: i32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RawRangedWeapon {
+    pub range: i32,
+    /// Dice expression such as `1d6+1`
+    pub damage: String,
+    pub ammo: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RawAmmunition {
+    pub count: i32,
+}
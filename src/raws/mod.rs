@@ -0,0 +1,87 @@
+mod entity_structs;
+mod rawmaster;
+pub use entity_structs::UnidentifiedKind;
+pub use rawmaster::*;
+use std::fs;
+
+/// Raw file read at startup. Editing it changes what spawns without
+/// rebuilding the game.
+const SPAWNS_PATH: &str = "./raws/spawns.json";
+
+/// Copy of the raw file built into the binary, used when the game is run
+/// from somewhere the raws folder can't be found.
+const EMBEDDED_SPAWNS: &str = include_str!("../../raws/spawns.json");
+
+pub fn load_raws() -> RawMaster {
+    let (source, data) = match fs::read_to_string(SPAWNS_PATH) {
+        Ok(data) => (SPAWNS_PATH, data),
+        Err(_) => ("embedded raws", EMBEDDED_SPAWNS.to_string()),
+    };
+    let raws =
+        serde_json::from_str(&data).unwrap_or_else(|e| panic!("Unable to parse {}: {}", source, e));
+    RawMaster::new(raws)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::entity_structs::Raws;
+    use super::*;
+    use crate::State;
+
+    fn parse(data: &str) -> Result<Raws, serde_json::Error> {
+        serde_json::from_str(data)
+    }
+
+    /// A world with only the given raws loaded
+    fn world_with(data: &str) -> State {
+        let mut gs = State::new(Some(1));
+        gs.ecs.insert(RawMaster::new(parse(data).unwrap()));
+        gs
+    }
+
+    #[test]
+    fn every_embedded_entity_spawns() {
+        let raws = parse(EMBEDDED_SPAWNS).expect("The embedded raws don't parse");
+        let mut gs = world_with(EMBEDDED_SPAWNS);
+        for entity in raws.entities.iter() {
+            assert!(spawn_named_entity(&mut gs.ecs, &entity.name, 1, 1).is_some());
+        }
+    }
+
+    #[test]
+    fn misspelled_components_are_refused() {
+        let error =
+            parse(r#"{ "entities": [{ "name": "Orc", "components": { "combat_stat": {} } }] }"#)
+                .unwrap_err();
+        assert!(error.to_string().contains("unknown field `combat_stat`"));
+    }
+
+    #[test]
+    fn missing_fields_are_refused() {
+        let error = parse(
+            r#"{ "entities": [{ "name": "Orc", "components": { "combat_stats": { "max_hp": 16 } } }] }"#,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("missing field"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Entity [Club] has invalid damage dice 2d")]
+    fn bad_damage_dice_name_the_entity() {
+        let mut gs = world_with(
+            r#"{ "entities": [{ "name": "Club", "components": { "melee_weapon": { "damage": "2d" } } }] }"#,
+        );
+        spawn_named_entity(&mut gs.ecs, "Club", 1, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Entity [Rat] has an invalid color brown")]
+    fn bad_colors_name_the_entity() {
+        let mut gs = world_with(
+            r##"{ "entities": [{ "name": "Rat", "components": {
+                "renderable": { "glyph": "r", "fg": "brown", "bg": "#000000", "order": 1 }
+            } }] }"##,
+        );
+        spawn_named_entity(&mut gs.ecs, "Rat", 1, 1);
+    }
+}
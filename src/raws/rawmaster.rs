@@ -0,0 +1,222 @@
+use super::entity_structs::{RawEntity, RawRenderable, Raws, UnidentifiedKind};
+use crate::{
+    initiative_system::NORMAL_SPEED, random_table::RandomTable, AiProfile, Ammunition,
+    AppliesStatus, AreaOfEffect, BlocksTile, CombatStats, Consumable, DefenseBonus, Energy,
+    EntryTrigger, Equippable, Hidden, InflictsDamage, Item, MagicMapper, MeleeWeapon, Monster,
+    MonsterBrain, Name, Position, ProvidesClairvoyance, ProvidesFood, ProvidesHealing, Ranged,
+    RangedWeapon, Renderable, SerializeMe, SingleActivation, Viewshed,
+};
+use rltk::RGB;
+use specs::prelude::*;
+use specs::saveload::{MarkedBuilder, SimpleMarker};
+use std::collections::HashMap;
+
+/// Loaded raw files, stored as a resource so the spawner can look up
+/// entities by name.
+pub struct RawMaster {
+    raws: Raws,
+    entity_index: HashMap<String, usize>,
+}
+
+impl RawMaster {
+    pub fn new(raws: Raws) -> RawMaster {
+        let mut entity_index = HashMap::new();
+        for (i, entity) in raws.entities.iter().enumerate() {
+            if entity_index.insert(entity.name.clone(), i).is_some() {
+                eprintln!("WARNING: duplicate entity name in raws [{}]", entity.name);
+            }
+        }
+        RawMaster { raws, entity_index }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&RawEntity> {
+        self.entity_index
+            .get(name)
+            .map(|idx| &self.raws.entities[*idx])
+    }
+
+    /// Names of every item that starts out unidentified, in file order
+    pub fn unidentified_items(&self) -> Vec<(String, UnidentifiedKind)> {
+        self.raws
+            .entities
+            .iter()
+            .filter_map(|entity| entity.unidentified.map(|kind| (entity.name.clone(), kind)))
+            .collect()
+    }
+
+    /// Weighted table of everything allowed to spawn at the given depth
+    pub fn spawn_table_for_depth(&self, depth: i32) -> RandomTable {
+        let mut table = RandomTable::new();
+        for entity in self.raws.entities.iter() {
+            if let Some(spawn) = &entity.spawn {
+                if depth >= spawn.min_depth && depth <= spawn.max_depth {
+                    let mut weight = spawn.weight;
+                    if spawn.add_map_depth_to_weight {
+                        weight += depth;
+                    }
+                    table = table.add(&entity.name, weight);
+                }
+            }
+        }
+        table
+    }
+}
+
+fn renderable(raw: &RawRenderable, name: &str) -> Renderable {
+    let glyph = raw
+        .glyph
+        .chars()
+        .next()
+        .unwrap_or_else(|| panic!("Entity [{}] has an empty glyph", name));
+    Renderable {
+        glyph: rltk::to_cp437(glyph),
+        fg: color(&raw.fg, name),
+        bg: color(&raw.bg, name),
+        render_order: raw.order,
+    }
+}
+
+fn color(code: &str, name: &str) -> RGB {
+    RGB::from_hex(code)
+        .unwrap_or_else(|_| panic!("Entity [{}] has an invalid color {}", name, code))
+}
+
+/// Creates the named entity at the given position. Returns None when no
+/// entity by that name has been loaded.
+pub fn spawn_named_entity(ecs: &mut World, name: &str, x: i32, y: i32) -> Option<Entity> {
+    let template = ecs.fetch::<RawMaster>().get(name).cloned()?;
+    let components = &template.components;
+
+    let mut eb = ecs.create_entity().with(Position { x, y }).with(Name {
+        name: template.name.clone(),
+    });
+
+    if let Some(raw) = &components.renderable {
+        eb = eb.with(renderable(raw, name));
+    }
+    if components.monster.is_some() {
+        eb = eb.with(Monster {});
+        eb = eb.with(MonsterBrain {
+            profile: components.ai.unwrap_or(AiProfile::Aggressive),
+            last_seen_player: None,
+        });
+    }
+    if components.blocks_tile.is_some() {
+        eb = eb.with(BlocksTile {});
+    }
+    if let Some(stats) = &components.combat_stats {
+        eb = eb.with(CombatStats {
+            max_hp: stats.max_hp,
+            hp: stats.max_hp,
+            defense: stats.defense,
+            power: stats.power,
+        });
+    }
+    if let Some(viewshed) = &components.viewshed {
+        eb = eb.with(Viewshed {
+            visible_tiles: Vec::new(),
+            range: viewshed.range,
+            dirty: true,
+        });
+    }
+    // Anything that acts needs energy to take turns with
+    if components.monster.is_some() || components.energy.is_some() {
+        let speed = components
+            .energy
+            .as_ref()
+            .map_or(NORMAL_SPEED, |energy| energy.speed);
+        eb = eb.with(Energy { speed, energy: 0 });
+    }
+    if components.item.is_some() {
+        eb = eb.with(Item {});
+    }
+    if components.consumable.is_some() {
+        eb = eb.with(Consumable {});
+    }
+    if let Some(healing) = &components.provides_healing {
+        eb = eb.with(ProvidesHealing {
+            heal_amount: healing.heal_amount,
+        });
+    }
+    if components.provides_food.is_some() {
+        eb = eb.with(ProvidesFood {});
+    }
+    if components.magic_mapper.is_some() {
+        eb = eb.with(MagicMapper {});
+    }
+    if let Some(clairvoyance) = &components.provides_clairvoyance {
+        eb = eb.with(ProvidesClairvoyance {
+            turns: clairvoyance.turns,
+        });
+    }
+    if let Some(ranged) = &components.ranged {
+        eb = eb.with(Ranged {
+            range: ranged.range,
+        });
+    }
+    if let Some(damage) = &components.inflicts_damage {
+        eb = eb.with(InflictsDamage {
+            damage: damage.damage,
+        });
+    }
+    if let Some(aoe) = &components.area_of_effect {
+        eb = eb.with(AreaOfEffect { radius: aoe.radius });
+    }
+    if let Some(effects) = &components.applies_status {
+        eb = eb.with(AppliesStatus {
+            effects: effects.clone(),
+        });
+    }
+    if let Some(equippable) = &components.equippable {
+        eb = eb.with(Equippable {
+            slot: equippable.slot,
+        });
+    }
+    if let Some(weapon) = &components.melee_weapon {
+        let dice = rltk::parse_dice_string(&weapon.damage).unwrap_or_else(|_| {
+            panic!(
+                "Entity [{}] has invalid damage dice {}",
+                name, weapon.damage
+            )
+        });
+        eb = eb.with(MeleeWeapon {
+            n_dice: dice.n_dice,
+            die_type: dice.die_type,
+            bonus: dice.bonus,
+        });
+    }
+    if let Some(bonus) = &components.defense_bonus {
+        eb = eb.with(DefenseBonus {
+            defense: bonus.defense,
+        });
+    }
+    if let Some(weapon) = &components.ranged_weapon {
+        let dice = rltk::parse_dice_string(&weapon.damage).unwrap_or_else(|_| {
+            panic!(
+                "Entity [{}] has invalid damage dice {}",
+                name, weapon.damage
+            )
+        });
+        eb = eb.with(RangedWeapon {
+            range: weapon.range,
+            n_dice: dice.n_dice,
+            die_type: dice.die_type,
+            bonus: dice.bonus,
+            ammo: weapon.ammo.clone(),
+        });
+    }
+    if let Some(ammo) = &components.ammunition {
+        eb = eb.with(Ammunition { count: ammo.count });
+    }
+    if components.hidden.is_some() {
+        eb = eb.with(Hidden {});
+    }
+    if components.entry_trigger.is_some() {
+        eb = eb.with(EntryTrigger {});
+    }
+    if components.single_activation.is_some() {
+        eb = eb.with(SingleActivation {});
+    }
+
+    Some(eb.marked::<SimpleMarker<SerializeMe>>().build())
+}
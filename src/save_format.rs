@@ -0,0 +1,274 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Gzip streams always start with these two bytes
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum Encoding {
+    Json,
+    Binary,
+}
+
+/// The encoding and compression a save is written with
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct Format {
+    pub encoding: Encoding,
+    pub compressed: bool,
+}
+
+impl Format {
+    /// What the game writes, picked by the `binary-saves` and
+    /// `compressed-saves` features
+    pub fn preferred() -> Format {
+        Format {
+            encoding: if cfg!(feature = "binary-saves") {
+                Encoding::Binary
+            } else {
+                Encoding::Json
+            },
+            compressed: cfg!(feature = "compressed-saves"),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let encoding = match self.encoding {
+            Encoding::Json => "JSON",
+            Encoding::Binary => "binary",
+        };
+        if self.compressed {
+            write!(f, "compressed {}", encoding)
+        } else {
+            write!(f, "{}", encoding)
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum FormatError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    #[cfg(feature = "binary-saves")]
+    Binary(String),
+    /// The save needs a feature this build was compiled without
+    #[cfg(not(all(feature = "binary-saves", feature = "compressed-saves")))]
+    NotSupported(&'static str),
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FormatError::Io(e) => write!(f, "the save file could not be accessed: {}", e),
+            FormatError::Json(e) => write!(f, "the save file is damaged: {}", e),
+            #[cfg(feature = "binary-saves")]
+            FormatError::Binary(e) => write!(f, "the save file is damaged: {}", e),
+            #[cfg(not(all(feature = "binary-saves", feature = "compressed-saves")))]
+            FormatError::NotSupported(feature) => write!(
+                f,
+                "the save needs a build with the {} feature to be read",
+                feature
+            ),
+        }
+    }
+}
+
+impl From<std::io::Error> for FormatError {
+    fn from(e: std::io::Error) -> Self {
+        FormatError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for FormatError {
+    fn from(e: serde_json::Error) -> Self {
+        FormatError::Json(e)
+    }
+}
+
+/// Reads a save in whichever format it was written with, so turning the
+/// save features on or off never strands an existing save. JSON saves from
+/// before the header existed are a run of separate values, and come back as
+/// one array of them.
+pub fn read(path: &Path) -> Result<Value, FormatError> {
+    decode(&fs::read(path)?)
+}
+
+/// Reads only the part of a save that `T` picks out, skipping over the rest
+/// without building it. JSON saves from before the header existed have no
+/// such part, and give `None`.
+pub fn read_part<T: DeserializeOwned>(path: &Path) -> Result<Option<T>, FormatError> {
+    decode_part(&fs::read(path)?)
+}
+
+fn decode_part<T: DeserializeOwned>(bytes: &[u8]) -> Result<Option<T>, FormatError> {
+    if bytes.starts_with(&GZIP_MAGIC) {
+        return decode_part(&decompress(bytes)?);
+    }
+
+    match bytes.iter().find(|byte| !byte.is_ascii_whitespace()) {
+        Some(b'[') => Ok(None),
+        Some(b'{') => Ok(Some(serde_json::from_slice(bytes)?)),
+        _ => decode_part_binary(bytes).map(Some),
+    }
+}
+
+fn decode(bytes: &[u8]) -> Result<Value, FormatError> {
+    if bytes.starts_with(&GZIP_MAGIC) {
+        return decode(&decompress(bytes)?);
+    }
+
+    let first = bytes.iter().find(|byte| !byte.is_ascii_whitespace());
+    if let Some(b'{') | Some(b'[') = first {
+        let mut values = serde_json::Deserializer::from_slice(bytes)
+            .into_iter::<Value>()
+            .collect::<Result<Vec<Value>, _>>()?;
+        if values.len() == 1 {
+            Ok(values.remove(0))
+        } else {
+            Ok(Value::Array(values))
+        }
+    } else {
+        decode_binary(bytes)
+    }
+}
+
+/// Writes the save in the given format, replacing whatever was at the path
+pub fn write<T: Serialize>(path: &Path, save: &T, format: Format) -> Result<(), FormatError> {
+    let mut writer = BufWriter::new(fs::File::create(path)?);
+    if format.compressed {
+        compress(&mut writer, save, format.encoding)?;
+    } else {
+        encode(&mut writer, save, format.encoding)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn encode<W: Write, T: Serialize>(
+    writer: W,
+    save: &T,
+    encoding: Encoding,
+) -> Result<(), FormatError> {
+    match encoding {
+        Encoding::Json => Ok(serde_json::to_writer(writer, save)?),
+        Encoding::Binary => encode_binary(writer, save),
+    }
+}
+
+#[cfg(feature = "binary-saves")]
+fn encode_binary<W: Write, T: Serialize>(writer: W, save: &T) -> Result<(), FormatError> {
+    ciborium::ser::into_writer(save, writer).map_err(|e| FormatError::Binary(e.to_string()))
+}
+
+#[cfg(not(feature = "binary-saves"))]
+fn encode_binary<W: Write, T: Serialize>(_writer: W, _save: &T) -> Result<(), FormatError> {
+    Err(FormatError::NotSupported("binary-saves"))
+}
+
+#[cfg(feature = "binary-saves")]
+fn decode_binary(bytes: &[u8]) -> Result<Value, FormatError> {
+    let value: ciborium::value::Value =
+        ciborium::de::from_reader(bytes).map_err(|e| FormatError::Binary(e.to_string()))?;
+    cbor_to_json(value)
+}
+
+/// Turns decoded CBOR into the value the same save would have had as JSON.
+/// CBOR keeps map keys such as depths as numbers, where JSON writes them as
+/// strings, and only strings can be read back as JSON keys.
+#[cfg(feature = "binary-saves")]
+fn cbor_to_json(value: ciborium::value::Value) -> Result<Value, FormatError> {
+    use ciborium::value::Value as Cbor;
+    use std::convert::TryFrom;
+    Ok(match value {
+        Cbor::Integer(integer) => {
+            let integer = i128::from(integer);
+            if let Ok(unsigned) = u64::try_from(integer) {
+                Value::from(unsigned)
+            } else if let Ok(signed) = i64::try_from(integer) {
+                Value::from(signed)
+            } else {
+                return Err(FormatError::Binary(format!("{} is too large", integer)));
+            }
+        }
+        Cbor::Bytes(bytes) => Value::from(bytes),
+        Cbor::Float(float) => Value::from(float),
+        Cbor::Text(text) => Value::String(text),
+        Cbor::Bool(boolean) => Value::Bool(boolean),
+        Cbor::Null => Value::Null,
+        Cbor::Tag(_, value) => cbor_to_json(*value)?,
+        Cbor::Array(values) => Value::Array(
+            values
+                .into_iter()
+                .map(cbor_to_json)
+                .collect::<Result<_, _>>()?,
+        ),
+        Cbor::Map(entries) => {
+            let mut map = serde_json::Map::new();
+            for (key, value) in entries {
+                let key = match cbor_to_json(key)? {
+                    Value::String(key) => key,
+                    Value::Number(key) => key.to_string(),
+                    other => return Err(FormatError::Binary(format!("{} can't be a key", other))),
+                };
+                map.insert(key, cbor_to_json(value)?);
+            }
+            Value::Object(map)
+        }
+        _ => return Err(FormatError::Binary("unknown CBOR value".to_string())),
+    })
+}
+
+#[cfg(feature = "binary-saves")]
+fn decode_part_binary<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, FormatError> {
+    ciborium::de::from_reader(bytes).map_err(|e| FormatError::Binary(e.to_string()))
+}
+
+#[cfg(not(feature = "binary-saves"))]
+fn decode_part_binary<T: DeserializeOwned>(_bytes: &[u8]) -> Result<T, FormatError> {
+    Err(FormatError::NotSupported("binary-saves"))
+}
+
+#[cfg(not(feature = "binary-saves"))]
+fn decode_binary(_bytes: &[u8]) -> Result<Value, FormatError> {
+    Err(FormatError::NotSupported("binary-saves"))
+}
+
+#[cfg(feature = "compressed-saves")]
+fn compress<W: Write, T: Serialize>(
+    writer: W,
+    save: &T,
+    encoding: Encoding,
+) -> Result<(), FormatError> {
+    let mut encoder = flate2::write::GzEncoder::new(writer, flate2::Compression::fast());
+    encode(&mut encoder, save, encoding)?;
+    encoder.finish()?;
+    Ok(())
+}
+
+#[cfg(not(feature = "compressed-saves"))]
+fn compress<W: Write, T: Serialize>(
+    _writer: W,
+    _save: &T,
+    _encoding: Encoding,
+) -> Result<(), FormatError> {
+    Err(FormatError::NotSupported("compressed-saves"))
+}
+
+#[cfg(feature = "compressed-saves")]
+fn decompress(bytes: &[u8]) -> Result<Vec<u8>, FormatError> {
+    use std::io::Read;
+    let mut inflated = Vec::new();
+    flate2::read::GzDecoder::new(bytes).read_to_end(&mut inflated)?;
+    Ok(inflated)
+}
+
+#[cfg(not(feature = "compressed-saves"))]
+fn decompress(_bytes: &[u8]) -> Result<Vec<u8>, FormatError> {
+    Err(FormatError::NotSupported("compressed-saves"))
+}
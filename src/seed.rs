@@ -0,0 +1,45 @@
+use rltk::RandomNumberGenerator;
+
+/// The seed a run was started with. Every level is generated from it, so
+/// the same seed always produces the same dungeon.
+pub struct WorldSeed {
+    pub seed: u64,
+}
+
+impl WorldSeed {
+    pub fn new(seed: u64) -> WorldSeed {
+        WorldSeed { seed }
+    }
+
+    /// Picks a seed at random for runs where the player didn't ask for one
+    pub fn random() -> WorldSeed {
+        WorldSeed::new(RandomNumberGenerator::new().next_u64())
+    }
+
+    /// Generator used to build and populate the level at the given depth.
+    /// Mixing the depth in keeps each level independent of how many rolls
+    /// were spent on the levels before it.
+    pub fn level_rng(&self, depth: i32) -> RandomNumberGenerator {
+        let depth_salt = (depth as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        RandomNumberGenerator::seeded(self.seed ^ depth_salt)
+    }
+
+    /// Generator used to make up the names of unidentified items, kept apart
+    /// from the level generators so the names never shift the dungeon layout
+    pub fn identification_rng(&self) -> RandomNumberGenerator {
+        RandomNumberGenerator::seeded(self.seed ^ 0xA5A5_5A5A_C3C3_3C3C)
+    }
+}
+
+/// Reads a seed given on the command line as `--seed <number>`
+pub fn seed_from_args() -> Option<u64> {
+    let args: Vec<String> = std::env::args().collect();
+    let position = args.iter().position(|arg| arg == "--seed")?;
+    match args.get(position + 1).map(|seed| seed.parse::<u64>()) {
+        Some(Ok(seed)) => Some(seed),
+        _ => {
+            eprintln!("--seed expects a positive whole number, picking one at random");
+            None
+        }
+    }
+}
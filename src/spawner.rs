@@ -1,76 +1,48 @@
-use std::collections::HashMap;
 extern crate rltk;
 use rltk::{RandomNumberGenerator, RGB};
 extern crate specs;
 use super::{
-  map::MAPWIDTH, random_table::RandomTable, AreaOfEffect, BlocksTile, CombatStats, Confusion,
-  Consumable, DefenseBonus, EquipmentSlot, Equippable, InflictsDamage, Item, MeleePowerBonus,
-  Monster, Name, Player, Position, ProvidesHealing, Ranged, Rect, Renderable, SerializeMe,
-  Viewshed,
+  initiative_system::NORMAL_SPEED,
+  map::MAPWIDTH,
+  raws::{spawn_named_entity, RawMaster},
+  CombatStats, Door, Energy, Experience, HungerClock, HungerState, Name, Player, Position,
+  Renderable, SerializeMe, Viewshed,
 };
 use crate::specs::saveload::{MarkedBuilder, SimpleMarker};
 use specs::prelude::*;
 
 const MAX_MONSTERS: i32 = 4;
 
-fn room_table(map_depth: i32) -> RandomTable {
-  RandomTable::new()
-    .add("Goblin", 10)
-    .add("Orc", 1 + map_depth)
-    .add("Health Potion", 7)
-    .add("Fireball Scroll", 2 + map_depth)
-    .add("Confusion Scroll", 2 + map_depth)
-    .add("Magic Missile Scroll", 4)
-    .add("Dagger", 3)
-    .add("Shield", 3)
-    .add("Longsword", -1 + map_depth)
-    .add("Tower Shield", -1 + map_depth)
-}
-
-/// Fills a room with stuff!
-pub fn spawn_room(ecs: &mut World, room: &Rect, map_depth: i32) {
-  let spawn_table = room_table(map_depth);
-  let mut spawn_points: HashMap<usize, String> = HashMap::new();
+/// Fills a region of the map with stuff!
+pub fn spawn_region(ecs: &mut World, area: &[usize], map_depth: i32) {
+  let spawn_table = ecs.fetch::<RawMaster>().spawn_table_for_depth(map_depth);
+  // Kept in the order they were rolled, so a seed always spawns the same
+  // entities in the same order
+  let mut spawn_points: Vec<(usize, String)> = Vec::new();
+  let mut areas: Vec<usize> = Vec::from(area);
 
   {
     let mut rng = ecs.write_resource::<RandomNumberGenerator>();
-    let num_spawns = rng.roll_dice(1, MAX_MONSTERS + 3) + (map_depth - 1) - 3;
+    let num_spawns = i32::min(
+      areas.len() as i32,
+      rng.roll_dice(1, MAX_MONSTERS + 3) + (map_depth - 1) - 3,
+    );
 
     for _i in 0..num_spawns {
-      let mut added = false;
-      let mut tries = 0;
-      while !added && tries < 20 {
-        let x = (room.x1 + rng.roll_dice(1, i32::abs(room.x2 - room.x1))) as usize;
-        let y = (room.y1 + rng.roll_dice(1, i32::abs(room.y2 - room.y1))) as usize;
-        let idx = (y * MAPWIDTH) + x;
-        if !spawn_points.contains_key(&idx) {
-          spawn_points.insert(idx, spawn_table.roll(&mut rng));
-          added = true;
-          tries += 1;
-        } else {
-          tries += 1;
-        }
-      }
+      let array_index = (rng.roll_dice(1, areas.len() as i32) - 1) as usize;
+      let map_idx = areas.remove(array_index);
+      spawn_points.push((map_idx, spawn_table.roll(&mut rng)));
     }
   }
 
   // Spawn everything
-  for spawn in spawn_points.iter() {
-    let x = (*spawn.0 % MAPWIDTH) as i32;
-    let y = (*spawn.0 / MAPWIDTH) as i32;
+  for (idx, name) in spawn_points.iter() {
+    let x = (*idx % MAPWIDTH) as i32;
+    let y = (*idx / MAPWIDTH) as i32;
 
-    match spawn.1.as_ref() {
-      "Goblin" => goblin(ecs, x, y),
-      "Orc" => orc(ecs, x, y),
-      "Health Potion" => health_potion(ecs, x, y),
-      "Fireball Scroll" => fireball_scroll(ecs, x, y),
-      "Confusion Scroll" => confusion_scroll(ecs, x, y),
-      "Magic Missile Scroll" => magic_missile_scroll(ecs, x, y),
-      "Dagger" => dagger(ecs, x, y),
-      "Shield" => shield(ecs, x, y),
-      "Longsword" => longsword(ecs, x, y),
-      "Tower Shield" => tower_shield(ecs, x, y),
-      _ => {}
+    // The table rolls "None" when nothing should spawn there
+    if name != "None" && spawn_named_entity(ecs, name, x, y).is_none() {
+      eprintln!("WARNING: no raw entity named [{}]", name);
     }
   }
 }
@@ -103,218 +75,35 @@ pub fn player(ecs: &mut World, player_x: i32, player_y: i32) -> Entity {
       range: 8,
       dirty: true,
     })
-    .marked::<SimpleMarker<SerializeMe>>()
-    .build()
-}
-
-fn monster<S: ToString>(ecs: &mut World, x: i32, y: i32, glyph: u8, name: S) {
-  ecs
-    .create_entity()
-    .with(Position { x, y })
-    .with(Renderable {
-      glyph,
-      fg: RGB::named(rltk::RED),
-      bg: RGB::named(rltk::BLACK),
-      render_order: 1,
-    })
-    .with(Monster {})
-    .with(CombatStats {
-      max_hp: 16,
-      hp: 16,
-      defense: 1,
-      power: 4,
-    })
-    .with(Name {
-      name: name.to_string(),
-    })
-    .with(Viewshed {
-      visible_tiles: Vec::new(),
-      range: 8,
-      dirty: true,
-    })
-    .with(BlocksTile {})
-    .marked::<SimpleMarker<SerializeMe>>()
-    .build();
-}
-
-fn orc(ecs: &mut World, x: i32, y: i32) {
-  monster(ecs, x, y, rltk::to_cp437('o'), "Orc");
-}
-
-fn goblin(ecs: &mut World, x: i32, y: i32) {
-  monster(ecs, x, y, rltk::to_cp437('g'), "Goblin");
-}
-
-// ---- Items ----
-
-fn health_potion(ecs: &mut World, x: i32, y: i32) {
-  ecs
-    .create_entity()
-    .with(Position { x, y })
-    .with(Renderable {
-      glyph: rltk::to_cp437('¡'),
-      fg: RGB::named(rltk::MAGENTA),
-      bg: RGB::named(rltk::BLACK),
-      render_order: 2,
-    })
-    .with(Name {
-      name: "Health Potion".to_string(),
-    })
-    .with(Item {})
-    .with(Consumable {})
-    .with(ProvidesHealing { heal_amount: 8 })
-    .marked::<SimpleMarker<SerializeMe>>()
-    .build();
-}
-
-fn magic_missile_scroll(ecs: &mut World, x: i32, y: i32) {
-  ecs
-    .create_entity()
-    .with(Position { x, y })
-    .with(Renderable {
-      glyph: rltk::to_cp437(')'),
-      fg: RGB::named(rltk::CYAN),
-      bg: RGB::named(rltk::BLACK),
-      render_order: 2,
-    })
-    .with(Name {
-      name: "Magic Missile Scroll".to_string(),
-    })
-    .with(Item {})
-    .with(Consumable {})
-    .with(Ranged { range: 6 })
-    .with(InflictsDamage { damage: 8 })
-    .marked::<SimpleMarker<SerializeMe>>()
-    .build();
-}
-
-fn fireball_scroll(ecs: &mut World, x: i32, y: i32) {
-  ecs
-    .create_entity()
-    .with(Position { x, y })
-    .with(Renderable {
-      glyph: rltk::to_cp437(')'),
-      fg: RGB::named(rltk::ORANGE),
-      bg: RGB::named(rltk::BLACK),
-      render_order: 2,
-    })
-    .with(Name {
-      name: "Fire Ball Scroll".to_string(),
-    })
-    .with(Item {})
-    .with(Consumable {})
-    .with(Ranged { range: 6 })
-    .with(InflictsDamage { damage: 20 })
-    .with(AreaOfEffect { radius: 3 })
-    .marked::<SimpleMarker<SerializeMe>>()
-    .build();
-}
-
-fn confusion_scroll(ecs: &mut World, x: i32, y: i32) {
-  ecs
-    .create_entity()
-    .with(Position { x, y })
-    .with(Renderable {
-      glyph: rltk::to_cp437(')'),
-      fg: RGB::named(rltk::PINK),
-      bg: RGB::named(rltk::BLACK),
-      render_order: 2,
-    })
-    .with(Name {
-      name: "Confusion Scroll".to_string(),
-    })
-    .with(Item {})
-    .with(Consumable {})
-    .with(Ranged { range: 6 })
-    .with(Confusion { turns: 4 })
-    .marked::<SimpleMarker<SerializeMe>>()
-    .build();
-}
-
-fn dagger(ecs: &mut World, x: i32, y: i32) {
-  ecs
-    .create_entity()
-    .with(Position { x, y })
-    .with(Renderable {
-      glyph: rltk::to_cp437('/'),
-      fg: RGB::named(rltk::CYAN),
-      bg: RGB::named(rltk::BLACK),
-      render_order: 2,
-    })
-    .with(Name {
-      name: "Dagger".to_string(),
-    })
-    .with(Item {})
-    .with(Equippable {
-      slot: EquipmentSlot::Melee,
-    })
-    .with(MeleePowerBonus { power: 2 })
-    .marked::<SimpleMarker<SerializeMe>>()
-    .build();
-}
-
-fn shield(ecs: &mut World, x: i32, y: i32) {
-  ecs
-    .create_entity()
-    .with(Position { x, y })
-    .with(Renderable {
-      glyph: rltk::to_cp437('('),
-      fg: RGB::named(rltk::CYAN),
-      bg: RGB::named(rltk::BLACK),
-      render_order: 2,
-    })
-    .with(Name {
-      name: "Shield".to_string(),
+    .with(HungerClock {
+      state: HungerState::WellFed,
+      duration: 20,
     })
-    .with(Item {})
-    .with(Equippable {
-      slot: EquipmentSlot::Shield,
+    .with(Experience { level: 1, xp: 0 })
+    .with(Energy {
+      speed: NORMAL_SPEED,
+      energy: 0,
     })
-    .with(DefenseBonus { defense: 1 })
     .marked::<SimpleMarker<SerializeMe>>()
-    .build();
-}
-
-fn longsword(ecs: &mut World, x: i32, y: i32) {
-  ecs
-    .create_entity()
-    .with(Position { x, y })
-    .with(Renderable {
-      glyph: rltk::to_cp437('/'),
-      fg: RGB::named(rltk::YELLOW),
-      bg: RGB::named(rltk::BLACK),
-      render_order: 2,
-    })
-    .with(Name {
-      name: "Longsword".to_string(),
-    })
-    .with(Item {})
-    .with(Equippable {
-      slot: EquipmentSlot::Melee,
-    })
-    .with(MeleePowerBonus { power: 4 })
-    .marked::<SimpleMarker<SerializeMe>>()
-    .build();
+    .build()
 }
 
-fn tower_shield(ecs: &mut World, x: i32, y: i32) {
+/// A closed door
+pub fn door(ecs: &mut World, x: i32, y: i32) -> Entity {
+  let door = Door { open: false };
   ecs
     .create_entity()
     .with(Position { x, y })
     .with(Renderable {
-      glyph: rltk::to_cp437('('),
-      fg: RGB::named(rltk::YELLOW),
+      glyph: door.glyph(),
+      fg: RGB::named(rltk::CHOCOLATE),
       bg: RGB::named(rltk::BLACK),
       render_order: 2,
     })
     .with(Name {
-      name: "Tower Shield".to_string(),
-    })
-    .with(Item {})
-    .with(Equippable {
-      slot: EquipmentSlot::Shield,
+      name: "Door".to_string(),
     })
-    .with(DefenseBonus { defense: 3 })
+    .with(door)
     .marked::<SimpleMarker<SerializeMe>>()
-    .build();
+    .build()
 }
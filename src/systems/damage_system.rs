@@ -1,5 +1,8 @@
 extern crate specs;
-use crate::{gamelog::GameLog, CombatStats, Map, Name, Player, Position, RunState, SufferDamage};
+use crate::{
+    gamelog::GameLog, morgue::RunStats, CombatStats, Experience, Map, Name, Player, Position,
+    RunState, SlainByPlayer, SufferDamage,
+};
 use specs::prelude::*;
 
 pub struct DamageSystem {}
@@ -11,15 +14,49 @@ impl<'a> System<'a> for DamageSystem {
         WriteStorage<'a, SufferDamage>,
         WriteExpect<'a, Map>,
         ReadStorage<'a, Position>,
+        WriteStorage<'a, SlainByPlayer>,
+        ReadStorage<'a, Player>,
+        WriteExpect<'a, RunStats>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, mut stats, mut damage, mut map, position) = data;
+        let (
+            entities,
+            mut stats,
+            mut damage,
+            mut map,
+            position,
+            mut slain_by_player,
+            players,
+            mut run_stats,
+        ) = data;
 
         // For each entity with stats and incoming damage minus all the incoming
         // damage from the hp stats of that entity.
-        for (entity, mut stats, damage) in (&entities, &mut stats, &damage).join() {
-            stats.hp -= damage.amount.iter().sum::<i32>();
+        for (entity, stats, damage) in (&entities, &mut stats, &damage).join() {
+            let was_alive = stats.hp > 0;
+            // The hit that takes the last of the health is what did them in
+            let mut killing_blow = None;
+            for (amount, _from_player, cause) in damage.amount.iter() {
+                stats.hp -= amount;
+                if killing_blow.is_none() && stats.hp < 1 {
+                    killing_blow = Some(cause);
+                }
+            }
+            // Credit the player if they had a hand in the killing blow
+            if was_alive
+                && stats.hp < 1
+                && damage.amount.iter().any(|(_, from_player, _)| *from_player)
+            {
+                slain_by_player
+                    .insert(entity, SlainByPlayer {})
+                    .expect("Unable to insert kill credit");
+            }
+            if let (true, Some(cause), Some(_player)) =
+                (was_alive, killing_blow, players.get(entity))
+            {
+                run_stats.killed_by = Some(cause.clone());
+            }
             let pos = position.get(entity);
             if let Some(pos) = pos {
                 let idx = map.xy_idx(pos.x, pos.y);
@@ -39,8 +76,12 @@ pub fn delete_the_dead(ecs: &mut World) {
         let combat_stats = ecs.read_storage::<CombatStats>();
         let players = ecs.read_storage::<Player>();
         let names = ecs.read_storage::<Name>();
+        let slain_by_player = ecs.read_storage::<SlainByPlayer>();
+        let mut experience = ecs.write_storage::<Experience>();
+        let player_entity = ecs.fetch::<Entity>();
         let entities = ecs.entities();
         let mut log = ecs.write_resource::<GameLog>();
+        let mut run_stats = ecs.write_resource::<RunStats>();
         for (entity, stats) in (&entities, &combat_stats).join() {
             if stats.hp < 1 {
                 let player = players.get(entity);
@@ -48,7 +89,18 @@ pub fn delete_the_dead(ecs: &mut World) {
                     None => {
                         let victim_name = names.get(entity);
                         if let Some(victim_name) = victim_name {
-                            log.entries.push(format!("{} is dead", &victim_name.name));
+                            log.combat(format!("{} is dead", &victim_name.name));
+                        }
+                        // Award experience for the kill
+                        if slain_by_player.get(entity).is_some() {
+                            if let Some(victim_name) = victim_name {
+                                *run_stats.kills.entry(victim_name.name.clone()).or_insert(0) += 1;
+                            }
+                            if let Some(player_xp) = experience.get_mut(*player_entity) {
+                                let gained = xp_value(stats);
+                                player_xp.xp += gained;
+                                log.combat(format!("You gain {} experience.", gained));
+                            }
                         }
                         dead.push(entity)
                     }
@@ -65,3 +117,8 @@ pub fn delete_the_dead(ecs: &mut World) {
         ecs.delete_entity(victim).expect("Unable to delete");
     }
 }
+
+/// Experience earned by killing something, growing with how tough it was
+pub fn xp_value(stats: &CombatStats) -> i32 {
+    (stats.max_hp + (stats.power + stats.defense) * 4) / 2
+}
@@ -0,0 +1,54 @@
+extern crate specs;
+use crate::{
+    flow_field::FlowField,
+    map::{Map, TerrainMap, MAPCOUNT},
+    RunState,
+};
+use rltk::Point;
+use specs::prelude::*;
+
+/// Fields every monster shares to chase the player or run from them
+pub struct PlayerFlowFields {
+    pub toward: FlowField,
+    pub away: FlowField,
+    /// The player tile the fields lead to, or away from
+    built_for: Option<usize>,
+}
+
+impl PlayerFlowFields {
+    pub fn new() -> PlayerFlowFields {
+        PlayerFlowFields {
+            toward: FlowField { values: Vec::new() },
+            away: FlowField { values: Vec::new() },
+            built_for: None,
+        }
+    }
+}
+
+/// Rebuilds the player flow fields once per player turn, rather than
+/// having every monster search its own path
+pub struct FlowFieldSystem {}
+
+impl<'a> System<'a> for FlowFieldSystem {
+    type SystemData = (
+        ReadExpect<'a, Map>,
+        ReadExpect<'a, Point>,
+        ReadExpect<'a, RunState>,
+        WriteExpect<'a, PlayerFlowFields>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (map, player_pos, runstate, mut fields) = data;
+
+        let player_idx = map.xy_idx(player_pos.x, player_pos.y);
+        let new_turn = *runstate == RunState::PlayerTurn || *runstate == RunState::PreRun;
+        if !new_turn && fields.built_for == Some(player_idx) {
+            return;
+        }
+
+        let terrain = TerrainMap { map: &map };
+        fields.toward = FlowField::toward(MAPCOUNT, &[player_idx], &terrain);
+        fields.away = FlowField::away(&fields.toward, &terrain);
+        fields.built_for = Some(player_idx);
+    }
+}
@@ -0,0 +1,80 @@
+extern crate specs;
+use crate::{gamelog::GameLog, HungerClock, HungerState, RunState, SufferDamage};
+use specs::prelude::*;
+
+/// How many turns each hunger state lasts before moving to the next one
+pub const HUNGER_STATE_DURATION: i32 = 200;
+/// Damage taken every turn while starving
+const STARVATION_DAMAGE: i32 = 1;
+
+pub struct HungerSystem {}
+
+impl<'a> System<'a> for HungerSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, HungerClock>,
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, RunState>,
+        WriteStorage<'a, SufferDamage>,
+        WriteExpect<'a, GameLog>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, mut hunger_clock, player_entity, runstate, mut inflict_damage, mut log) =
+            data;
+
+        // The clock only ticks once per round, on the player's turn
+        if *runstate != RunState::PlayerTurn {
+            return;
+        }
+
+        for (entity, clock) in (&entities, &mut hunger_clock).join() {
+            let is_player = entity == *player_entity;
+            clock.duration -= 1;
+            if clock.duration > 0 {
+                continue;
+            }
+
+            match clock.state {
+                HungerState::WellFed => {
+                    clock.state = HungerState::Normal;
+                    clock.duration = HUNGER_STATE_DURATION;
+                    if is_player {
+                        log.system("You are no longer well fed.".to_string());
+                    }
+                }
+                HungerState::Normal => {
+                    clock.state = HungerState::Hungry;
+                    clock.duration = HUNGER_STATE_DURATION;
+                    if is_player {
+                        log.system("You are hungry.".to_string());
+                    }
+                }
+                HungerState::Hungry => {
+                    clock.state = HungerState::Starving;
+                    clock.duration = HUNGER_STATE_DURATION;
+                    if is_player {
+                        log.system("You are starving!".to_string());
+                    }
+                }
+                HungerState::Starving => {
+                    // Stay starving, and hurt every turn until something is eaten
+                    clock.duration = 0;
+                    if is_player {
+                        log.system(
+                            "Your hunger pangs are getting painful! You suffer 1 hp damage."
+                                .to_string(),
+                        );
+                    }
+                    SufferDamage::new_damage(
+                        &mut inflict_damage,
+                        entity,
+                        STARVATION_DAMAGE,
+                        false,
+                        "starvation",
+                    );
+                }
+            }
+        }
+    }
+}
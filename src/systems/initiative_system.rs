@@ -0,0 +1,88 @@
+extern crate specs;
+use crate::{Energy, MyTurn, Position, RunState, StatusEffectType, StatusEffects};
+use specs::prelude::*;
+
+/// Energy gained each tick by something moving at normal speed
+pub const NORMAL_SPEED: i32 = 100;
+/// Energy spent by taking a turn. At normal speed that is one turn every
+/// ten ticks.
+pub const ACTION_COST: i32 = 1000;
+
+/// Game time, counted in ticks. Timed effects follow it rather than anyone's
+/// turns, so being hasted or slowed doesn't change how long they last.
+#[derive(Default)]
+pub struct GameClock {
+    ticks: i32,
+    /// Set for the tick that ends a round, the time a turn takes at normal
+    /// speed
+    pub round_ended: bool,
+}
+
+/// Hands out turns. Every tick each entity on the level gains energy
+/// according to its speed, and those with enough to act spend it and get a
+/// turn. When the player gets a turn the game goes back to waiting for input.
+pub struct InitiativeSystem {}
+
+impl<'a> System<'a> for InitiativeSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, Energy>,
+        WriteStorage<'a, MyTurn>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, StatusEffects>,
+        ReadExpect<'a, Entity>,
+        WriteExpect<'a, RunState>,
+        WriteExpect<'a, GameClock>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut energies,
+            mut turns,
+            positions,
+            status_effects,
+            player_entity,
+            mut runstate,
+            mut clock,
+        ) = data;
+
+        clock.round_ended = false;
+        if *runstate != RunState::Ticking {
+            return;
+        }
+        clock.ticks += NORMAL_SPEED;
+        if clock.ticks >= ACTION_COST {
+            clock.ticks -= ACTION_COST;
+            clock.round_ended = true;
+        }
+
+        // Only entities on the current level have a position, so anything
+        // left on other levels waits where it is
+        for (entity, energy, _pos) in (&entities, &mut energies, &positions).join() {
+            energy.energy += effective_speed(energy.speed, status_effects.get(entity));
+            if energy.energy < ACTION_COST {
+                continue;
+            }
+            energy.energy -= ACTION_COST;
+
+            if entity == *player_entity {
+                *runstate = RunState::AwaitingInput;
+            } else {
+                turns
+                    .insert(entity, MyTurn {})
+                    .expect("Unable to insert turn");
+            }
+        }
+    }
+}
+
+/// Speed after haste and slow are taken into account
+pub fn effective_speed(speed: i32, status: Option<&StatusEffects>) -> i32 {
+    match status {
+        Some(status) if status.has(StatusEffectType::Haste) => speed * 2,
+        Some(status) if status.has(StatusEffectType::Slow) => speed / 2,
+        _ => speed,
+    }
+}
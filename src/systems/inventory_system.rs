@@ -1,8 +1,10 @@
 use crate::{
-  gamelog::GameLog, map::Map, particle_system::ParticleBuilder, AreaOfEffect, CombatStats,
-  Confusion, Consumable, Equippable, Equipped, InBackpack, InflictsDamage, Name, Position,
-  ProvidesHealing, SufferDamage, WantsToDropItem, WantsToPickupItem, WantsToUnequipItem,
-  WantsToUseItem,
+  gamelog::GameLog, hunger_system::HUNGER_STATE_DURATION, identification::IdentificationTable,
+  map::Map, particle_system::ParticleBuilder, Ammunition, AppliesStatus, AreaOfEffect, CombatStats,
+  Consumable, Equippable, Equipped, Hidden, HungerClock, HungerState, InBackpack, InflictsDamage,
+  MagicMapper, Name, Position, ProvidesClairvoyance, ProvidesFood, ProvidesHealing, RunState,
+  StatusEffect, StatusEffectType, StatusEffects, SufferDamage, WantsToDropItem, WantsToPickupItem,
+  WantsToUnequipItem, WantsToUseItem,
 };
 use specs::prelude::*;
 
@@ -16,13 +18,46 @@ impl<'a> System<'a> for ItemCollectionSystem {
     WriteStorage<'a, Position>,
     ReadStorage<'a, Name>,
     WriteStorage<'a, InBackpack>,
+    Entities<'a>,
+    WriteStorage<'a, Ammunition>,
+    ReadExpect<'a, IdentificationTable>,
   );
 
   fn run(&mut self, data: Self::SystemData) {
-    let (player_entity, mut gamelog, mut wants_pickup, mut positions, names, mut backpack) = data;
+    let (
+      player_entity,
+      mut gamelog,
+      mut wants_pickup,
+      mut positions,
+      names,
+      mut backpack,
+      entities,
+      mut ammunition,
+      identification,
+    ) = data;
 
     for pickup in wants_pickup.join() {
       positions.remove(pickup.item);
+
+      // Ammunition joins a stack of the same kind already being carried
+      if let Some(picked_up) = ammunition.get(pickup.item).map(|ammo| ammo.count) {
+        let item_name = &names.get(pickup.item).unwrap().name;
+        let stack =
+          (&entities, &backpack, &names, &mut ammunition)
+            .join()
+            .find(|(_e, pack, name, _ammo)| {
+              pack.owner == pickup.collected_by && name.name == *item_name
+            });
+        if let Some((_e, _pack, _name, ammo)) = stack {
+          ammo.count += picked_up;
+          entities.delete(pickup.item).expect("Delete failed");
+          if pickup.collected_by == *player_entity {
+            gamelog.loot(format!("You pick up {} {}.", picked_up, item_name));
+          }
+          continue;
+        }
+      }
+
       backpack
         .insert(
           pickup.item,
@@ -33,9 +68,9 @@ impl<'a> System<'a> for ItemCollectionSystem {
         .expect("Unable to insert backpack entry");
 
       if pickup.collected_by == *player_entity {
-        gamelog.entries.push(format!(
+        gamelog.loot(format!(
           "You pick up the {}.",
-          names.get(pickup.item).unwrap().name
+          identification.display_name(&names.get(pickup.item).unwrap().name)
         ));
       }
     }
@@ -60,12 +95,20 @@ impl<'a> System<'a> for ItemUseSystem {
     ReadStorage<'a, AreaOfEffect>,
     ReadExpect<'a, Map>,
     WriteStorage<'a, SufferDamage>,
-    WriteStorage<'a, Confusion>,
+    ReadStorage<'a, AppliesStatus>,
+    WriteStorage<'a, StatusEffects>,
     WriteStorage<'a, Equippable>,
     WriteStorage<'a, Equipped>,
     WriteStorage<'a, InBackpack>,
     WriteExpect<'a, ParticleBuilder>,
     ReadStorage<'a, Position>,
+    ReadStorage<'a, ProvidesFood>,
+    WriteStorage<'a, HungerClock>,
+    WriteExpect<'a, IdentificationTable>,
+    ReadStorage<'a, MagicMapper>,
+    ReadStorage<'a, ProvidesClairvoyance>,
+    WriteExpect<'a, RunState>,
+    ReadStorage<'a, Hidden>,
   );
 
   fn run(&mut self, data: Self::SystemData) {
@@ -82,17 +125,35 @@ impl<'a> System<'a> for ItemUseSystem {
       aoe,
       map,
       mut suffer_damage,
-      mut confused,
+      applies_status,
+      mut status_effects,
       equippable,
       mut equipped,
       mut backpack,
       mut particle_builder,
       positions,
+      provides_food,
+      mut hunger_clocks,
+      mut identification,
+      magic_mappers,
+      provides_clairvoyance,
+      mut runstate,
+      hidden,
     ) = data;
 
     // Using items
     for (entity, useitem) in (&entities, &wants_to_use).join() {
-      // Targeting
+      // Using something is the surest way to find out what it is
+      if entity == *player_entity {
+        let real_name = &names.get(useitem.item).unwrap().name;
+        let known_as = identification.display_name(real_name);
+        if identification.identify(real_name) {
+          gamelog.loot(format!("The {} was a {}!", known_as, real_name));
+        }
+      }
+
+      // Targeting. Hidden things, such as traps nobody has found, are
+      // passed over.
       let mut targets: Vec<Entity> = Vec::new();
       match useitem.target {
         None => {
@@ -105,7 +166,9 @@ impl<'a> System<'a> for ItemUseSystem {
               // Single point target
               let idx = map.xy_idx(target.x, target.y);
               for mob in map.tile_content[idx].iter() {
-                targets.push(*mob);
+                if hidden.get(*mob).is_none() {
+                  targets.push(*mob);
+                }
               }
             }
             Some(area_effect) => {
@@ -116,7 +179,9 @@ impl<'a> System<'a> for ItemUseSystem {
               for tile_idx in blast_tiles.iter() {
                 let idx = map.xy_idx(tile_idx.x, tile_idx.y);
                 for mob in map.tile_content[idx].iter() {
-                  targets.push(*mob);
+                  if hidden.get(*mob).is_none() {
+                    targets.push(*mob);
+                  }
                 }
                 particle_builder.request(
                   tile_idx.x,
@@ -146,7 +211,7 @@ impl<'a> System<'a> for ItemUseSystem {
             if already_equipped.owner == target && already_equipped.slot == equipment.slot {
               to_unequip.push(item_entity);
               if target == *player_entity {
-                gamelog.entries.push(format!("You unequip {}.", name.name))
+                gamelog.loot(format!("You unequip {}.", name.name))
               }
             }
           }
@@ -171,7 +236,7 @@ impl<'a> System<'a> for ItemUseSystem {
           if target == *player_entity {
             let name = names.get(useitem.item);
             if let Some(name) = name {
-              gamelog.entries.push(format!("You equipped {}", name.name))
+              gamelog.loot(format!("You equipped {}", name.name))
             }
           }
         }
@@ -187,7 +252,7 @@ impl<'a> System<'a> for ItemUseSystem {
             if let Some(stats) = stats {
               stats.hp = i32::min(stats.max_hp, stats.hp + healer.heal_amount);
               if entity == *player_entity {
-                gamelog.entries.push(format!(
+                gamelog.loot(format!(
                   "You drink the {}, healing {} hp.",
                   names.get(useitem.item).unwrap().name,
                   healer.heal_amount
@@ -209,17 +274,65 @@ impl<'a> System<'a> for ItemUseSystem {
         }
       }
 
+      // Eating resets the hunger clock
+      let item_edible = provides_food.get(useitem.item);
+      match item_edible {
+        None => {}
+        Some(_) => {
+          let target = targets[0];
+          let hunger_clock = hunger_clocks.get_mut(target);
+          if let Some(hunger_clock) = hunger_clock {
+            hunger_clock.state = HungerState::WellFed;
+            hunger_clock.duration = HUNGER_STATE_DURATION;
+            if target == *player_entity {
+              gamelog.loot(format!(
+                "You eat the {}.",
+                names.get(useitem.item).unwrap().name
+              ));
+            }
+          }
+        }
+      }
+
+      // Magic mapping sweeps the level into view over the next few frames
+      if magic_mappers.get(useitem.item).is_some() && entity == *player_entity {
+        gamelog.loot("The layout of the level unfolds in your mind!".to_string());
+        *runstate = RunState::MagicMapReveal { row: 0 };
+      }
+
+      if let Some(clairvoyance) = provides_clairvoyance.get(useitem.item) {
+        let target = targets[0];
+        StatusEffects::apply(
+          &mut status_effects,
+          target,
+          StatusEffect {
+            kind: StatusEffectType::Clairvoyance,
+            turns: clairvoyance.turns,
+            potency: 0,
+          },
+        );
+        if target == *player_entity {
+          gamelog.loot("You sense the creatures around you.".to_string());
+        }
+      }
+
       let item_damages = inflict_damage.get(useitem.item);
       match item_damages {
         None => {}
         Some(damager) => {
           for target in targets.iter() {
-            SufferDamage::new_damage(&mut suffer_damage, *target, damager.damage);
+            SufferDamage::new_damage(
+              &mut suffer_damage,
+              *target,
+              damager.damage,
+              entity == *player_entity,
+              &names.get(useitem.item).unwrap().name,
+            );
 
             if entity == *player_entity {
               let mob_name = names.get(*target).unwrap();
               let item_name = names.get(useitem.item).unwrap();
-              gamelog.entries.push(format!(
+              gamelog.combat(format!(
                 "Did {} damage to {} with {}",
                 damager.damage, mob_name.name, item_name.name
               ))
@@ -240,44 +353,44 @@ impl<'a> System<'a> for ItemUseSystem {
         }
       }
 
-      // Can it pass along confusion? Note the use of scopes to escape from the borrow checker!
-      let mut add_confusion = Vec::new();
-      {
-        let causes_confusion = confused.get(useitem.item);
-        match causes_confusion {
-          None => {}
-          Some(confusion) => {
-            for mob in targets.iter() {
-              add_confusion.push((*mob, confusion.turns));
-              if entity == *player_entity {
-                let mob_name = names.get(*mob).unwrap();
-                let item_name = names.get(useitem.item).unwrap();
-                gamelog.entries.push(format!(
-                  "You use {} on {}, confusing them.",
-                  item_name.name, mob_name.name
+      // Can it pass along status effects?
+      if let Some(applies) = applies_status.get(useitem.item) {
+        for target in targets.iter().filter(|t| combat_stats.get(**t).is_some()) {
+          for effect in applies.effects.iter() {
+            StatusEffects::apply(&mut status_effects, *target, *effect);
+            if entity == *player_entity {
+              let item_name = names.get(useitem.item).unwrap();
+              if *target == *player_entity {
+                gamelog.loot(format!(
+                  "You use {}, and are now {}.",
+                  item_name.name,
+                  effect.kind.name().to_lowercase()
+                ));
+              } else {
+                let mob_name = names.get(*target).unwrap();
+                gamelog.combat(format!(
+                  "You use {} on {}, leaving them {}.",
+                  item_name.name,
+                  mob_name.name,
+                  effect.kind.name().to_lowercase()
                 ));
-              }
-
-              let pos = positions.get(*mob);
-              if let Some(pos) = pos {
-                particle_builder.request(
-                  pos.x,
-                  pos.y,
-                  rltk::RGB::named(rltk::MAGENTA),
-                  rltk::RGB::named(rltk::BLACK),
-                  rltk::to_cp437('?'),
-                  200.0,
-                );
               }
             }
           }
+
+          let pos = positions.get(*target);
+          if let Some(pos) = pos {
+            particle_builder.request(
+              pos.x,
+              pos.y,
+              rltk::RGB::named(rltk::MAGENTA),
+              rltk::RGB::named(rltk::BLACK),
+              rltk::to_cp437('?'),
+              200.0,
+            );
+          }
         }
       }
-      for mob in add_confusion.iter() {
-        confused
-          .insert(mob.0, Confusion { turns: mob.1 })
-          .expect("Unable to insert status");
-      }
 
       let consumable = consumables.get(useitem.item);
       match consumable {
@@ -302,11 +415,20 @@ impl<'a> System<'a> for ItemDropSystem {
     ReadStorage<'a, Name>,
     WriteStorage<'a, Position>,
     WriteStorage<'a, InBackpack>,
+    ReadExpect<'a, IdentificationTable>,
   );
 
   fn run(&mut self, data: Self::SystemData) {
-    let (player_entity, mut gamelog, entities, mut wants_drop, names, mut positions, mut backpack) =
-      data;
+    let (
+      player_entity,
+      mut gamelog,
+      entities,
+      mut wants_drop,
+      names,
+      mut positions,
+      mut backpack,
+      identification,
+    ) = data;
 
     for (entity, to_drop) in (&entities, &wants_drop).join() {
       let mut dropper_pos: Position = Position { x: 0, y: 0 };
@@ -327,9 +449,9 @@ impl<'a> System<'a> for ItemDropSystem {
       backpack.remove(to_drop.item);
 
       if entity == *player_entity {
-        gamelog.entries.push(format!(
+        gamelog.loot(format!(
           "You drop the {}.",
-          names.get(to_drop.item).unwrap().name
+          identification.display_name(&names.get(to_drop.item).unwrap().name)
         ));
       }
     }
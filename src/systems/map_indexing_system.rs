@@ -1,5 +1,5 @@
 extern crate specs;
-use crate::{map::Map, BlocksTile, Position};
+use crate::{map::Map, BlocksTile, Door, EntryTrigger, Hidden, Position};
 use specs::prelude::*;
 
 pub struct MapIndexingSystem {}
@@ -9,16 +9,21 @@ impl<'a> System<'a> for MapIndexingSystem {
         WriteExpect<'a, Map>,
         ReadStorage<'a, Position>,
         ReadStorage<'a, BlocksTile>,
+        ReadStorage<'a, Door>,
+        ReadStorage<'a, EntryTrigger>,
+        ReadStorage<'a, Hidden>,
         Entities<'a>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut map, position, blockers, entities) = data;
+        let (mut map, position, blockers, doors, triggers, hidden, entities) = data;
 
         // Populate blocked on map
         map.populate_blocked();
         // Clear all entities on map
         map.clear_content_index();
+        map.view_blocked.clear();
+        map.known_traps.clear();
 
         // For each entity that has a position on the map
         for (position, entity) in (&position, &entities).join() {
@@ -32,6 +37,16 @@ impl<'a> System<'a> for MapIndexingSystem {
                 map.blocked[idx] = true;
             }
 
+            // Closed doors can't be seen through. They don't block the tile,
+            // so that pathing goes through them and opens them on arrival.
+            if doors.get(entity).is_some_and(|door| !door.open) {
+                map.view_blocked.insert(idx);
+            }
+
+            if triggers.get(entity).is_some() && hidden.get(entity).is_none() {
+                map.known_traps.insert(idx);
+            }
+
             // Add entity to list of entities for this location in the map.
             map.tile_content[idx].push(entity);
         }
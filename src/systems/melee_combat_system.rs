@@ -1,10 +1,52 @@
 extern crate specs;
 use crate::{
-    gamelog::GameLog, particle_system::ParticleBuilder, CombatStats, DefenseBonus, Equipped,
-    MeleePowerBonus, Name, Position, SufferDamage, WantsToMelee,
+    gamelog::GameLog, particle_system::ParticleBuilder, AppliesStatus, CombatStats, DefenseBonus,
+    EquipmentSlot, Equipped, MeleeWeapon, Name, Position, StatusEffects, SufferDamage,
+    WantsToMelee,
 };
+use rltk::RandomNumberGenerator;
 use specs::prelude::*;
 
+/// Armour class of something with no defense at all
+const BASE_ARMOUR_CLASS: i32 = 10;
+/// Damage done without a weapon
+const UNARMED: MeleeWeapon = MeleeWeapon {
+    n_dice: 1,
+    die_type: 4,
+    bonus: 0,
+};
+
+/// How an attack roll turned out
+pub enum AttackRoll {
+    Miss,
+    Hit { damage: i32, critical: bool },
+}
+
+/// Rolls to hit against the target's armour class. A natural 1 always
+/// misses and a natural 20 always lands, as a critical hit that rolls the
+/// damage dice twice. Ranged attacks roll the same way.
+pub fn roll_attack(
+    rng: &mut RandomNumberGenerator,
+    power: i32,
+    defense: i32,
+    (n_dice, die_type, bonus): (i32, i32, i32),
+) -> AttackRoll {
+    let natural_roll = rng.roll_dice(1, 20);
+    let armour_class = BASE_ARMOUR_CLASS + defense;
+    let critical = natural_roll == 20;
+    if !critical && (natural_roll == 1 || natural_roll + power < armour_class) {
+        return AttackRoll::Miss;
+    }
+    let mut damage = rng.roll_dice(n_dice, die_type);
+    if critical {
+        damage += rng.roll_dice(n_dice, die_type);
+    }
+    AttackRoll::Hit {
+        damage: i32::max(1, damage + bonus + power / 2),
+        critical,
+    }
+}
+
 pub struct MeleeCombatSystem {}
 
 impl<'a> System<'a> for MeleeCombatSystem {
@@ -13,13 +55,17 @@ impl<'a> System<'a> for MeleeCombatSystem {
         WriteStorage<'a, WantsToMelee>,
         ReadStorage<'a, Name>,
         ReadStorage<'a, CombatStats>,
-        ReadStorage<'a, MeleePowerBonus>,
+        ReadStorage<'a, MeleeWeapon>,
         ReadStorage<'a, DefenseBonus>,
         ReadStorage<'a, Equipped>,
         WriteStorage<'a, SufferDamage>,
         WriteExpect<'a, GameLog>,
         WriteExpect<'a, ParticleBuilder>,
         ReadStorage<'a, Position>,
+        ReadExpect<'a, Entity>,
+        ReadStorage<'a, AppliesStatus>,
+        WriteStorage<'a, StatusEffects>,
+        WriteExpect<'a, RandomNumberGenerator>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -28,13 +74,17 @@ impl<'a> System<'a> for MeleeCombatSystem {
             mut wants_melee,
             names,
             combat_stats,
-            melee_bonuses,
+            melee_weapons,
             defense_bonuses,
             equipped,
             mut inflict_damage,
             mut log,
             mut particle_builder,
             positions,
+            player_entity,
+            applies_status,
+            mut status_effects,
+            mut rng,
         ) = data;
 
         // For all entities that want to melee and have stats
@@ -43,14 +93,7 @@ impl<'a> System<'a> for MeleeCombatSystem {
         {
             // Only allow monsters to attack if they aren't already dead
             if stats.hp > 0 {
-                let mut offensive_bonus = 0;
-                for (_item_entity, equipped_item, melee_bonus) in
-                    (&entities, &equipped, &melee_bonuses).join()
-                {
-                    if equipped_item.owner == attacker {
-                        offensive_bonus += melee_bonus.power;
-                    }
-                }
+                let weapon = melee_weapon(attacker, &entities, &melee_weapons, &equipped);
 
                 // Get combat stats for entity taking the damage
                 let target_stats = combat_stats.get(wants_melee.target).unwrap();
@@ -78,24 +121,50 @@ impl<'a> System<'a> for MeleeCombatSystem {
                             200.0,
                         );
                     }
-                    // Calculate damage and set it as zero if less than zero
-                    // Attacks shouldn't heal :)
-                    let damage = i32::max(
-                        0,
-                        (stats.power + offensive_bonus) - (target_stats.defense + defensive_bonus),
+                    let roll = roll_attack(
+                        &mut rng,
+                        stats.power,
+                        target_stats.defense + defensive_bonus,
+                        (weapon.n_dice, weapon.die_type, weapon.bonus),
                     );
 
-                    if damage == 0 {
-                        log.entries.push(format!(
-                            "{} is unable to hurt {}",
-                            &name.name, &target_name.name
-                        ));
+                    if let AttackRoll::Hit { damage, critical } = roll {
+                        if critical {
+                            log.combat(format!(
+                                "{} critically hits {}, for {} hp!",
+                                &name.name, &target_name.name, damage
+                            ));
+                        } else {
+                            log.combat(format!(
+                                "{} hits {}, for {} hp.",
+                                &name.name, &target_name.name, damage
+                            ));
+                        }
+                        SufferDamage::new_damage(
+                            &mut inflict_damage,
+                            wants_melee.target,
+                            damage,
+                            attacker == *player_entity,
+                            &name.name,
+                        );
+
+                        // Venomous bites and the like
+                        if let Some(applies) = applies_status.get(attacker) {
+                            for effect in applies.effects.iter() {
+                                StatusEffects::apply(
+                                    &mut status_effects,
+                                    wants_melee.target,
+                                    *effect,
+                                );
+                                log.combat(format!(
+                                    "{} is {}!",
+                                    &target_name.name,
+                                    effect.kind.name().to_lowercase()
+                                ));
+                            }
+                        }
                     } else {
-                        log.entries.push(format!(
-                            "{} hits {}, for {} hp.",
-                            &name.name, &target_name.name, damage
-                        ));
-                        SufferDamage::new_damage(&mut inflict_damage, wants_melee.target, damage);
+                        log.combat(format!("{} misses {}.", &name.name, &target_name.name));
                     }
                 }
             }
@@ -105,3 +174,22 @@ impl<'a> System<'a> for MeleeCombatSystem {
         wants_melee.clear();
     }
 }
+
+/// The weapon an entity attacks with: its natural weapon, the one it has
+/// equipped, or its fists
+fn melee_weapon(
+    entity: Entity,
+    entities: &Entities,
+    melee_weapons: &ReadStorage<MeleeWeapon>,
+    equipped: &ReadStorage<Equipped>,
+) -> MeleeWeapon {
+    if let Some(weapon) = melee_weapons.get(entity) {
+        return weapon.clone();
+    }
+    (entities, equipped, melee_weapons)
+        .join()
+        .find(|(_e, equipped, _weapon)| {
+            equipped.owner == entity && equipped.slot == EquipmentSlot::Melee
+        })
+        .map_or(UNARMED, |(_e, _equipped, weapon)| weapon.clone())
+}
@@ -1,8 +1,14 @@
 pub mod damage_system;
+pub mod flow_field_system;
+pub mod hunger_system;
+pub mod initiative_system;
 pub mod inventory_system;
 pub mod map_indexing_system;
 pub mod melee_combat_system;
 pub mod monster_ai_system;
 pub mod particle_system;
+pub mod ranged_combat_system;
 pub mod saveload_system;
+pub mod status_effect_system;
+pub mod trigger_system;
 pub mod visibility_system;
@@ -1,11 +1,24 @@
 extern crate specs;
 use crate::{
-    map::Map, particle_system::ParticleBuilder, Confusion, Monster, Position, RunState, Viewshed,
-    WantsToMelee,
+    flow_field_system::PlayerFlowFields, gamelog::GameLog, map::Map,
+    particle_system::ParticleBuilder, visibility_system, AiProfile, CombatStats, Door, EntityMoved,
+    Monster, MonsterBrain, MyTurn, Name, Position, RangedWeapon, Renderable, StatusEffectType,
+    StatusEffects, Viewshed, WantsToMelee, WantsToShoot,
 };
 use specs::prelude::*;
 extern crate rltk;
-use rltk::Point;
+use rltk::{Point, RandomNumberGenerator};
+
+/// How far a pack monster's call for help carries
+const CALL_RANGE: f32 = 10.0;
+
+/// What a monster decided to do with its turn
+enum Action {
+    Wait,
+    Melee,
+    Shoot,
+    Step(usize),
+}
 
 pub struct MonsterAI {}
 
@@ -15,14 +28,25 @@ impl<'a> System<'a> for MonsterAI {
         WriteExpect<'a, Map>,
         ReadExpect<'a, Point>,
         ReadExpect<'a, Entity>,
-        ReadExpect<'a, RunState>,
         Entities<'a>,
         WriteStorage<'a, Viewshed>,
         ReadStorage<'a, Monster>,
         WriteStorage<'a, Position>,
         WriteStorage<'a, WantsToMelee>,
-        WriteStorage<'a, Confusion>,
+        ReadStorage<'a, StatusEffects>,
         WriteExpect<'a, ParticleBuilder>,
+        WriteStorage<'a, MyTurn>,
+        ReadStorage<'a, RangedWeapon>,
+        WriteStorage<'a, WantsToShoot>,
+        WriteStorage<'a, Door>,
+        WriteStorage<'a, Renderable>,
+        WriteStorage<'a, EntityMoved>,
+        WriteStorage<'a, MonsterBrain>,
+        ReadStorage<'a, CombatStats>,
+        ReadStorage<'a, Name>,
+        WriteExpect<'a, RandomNumberGenerator>,
+        WriteExpect<'a, GameLog>,
+        ReadExpect<'a, PlayerFlowFields>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -30,30 +54,46 @@ impl<'a> System<'a> for MonsterAI {
             mut map,
             player_pos,
             player_entity,
-            runstate,
             entities,
             mut viewshed,
             monster,
             mut position,
             mut wants_to_melee,
-            mut confused,
+            status_effects,
             mut particle_builder,
+            mut turns,
+            ranged_weapons,
+            mut wants_to_shoot,
+            mut doors,
+            mut renderables,
+            mut entity_moved,
+            mut brains,
+            combat_stats,
+            names,
+            mut rng,
+            mut log,
+            fields,
         ) = data;
 
-        if *runstate != RunState::MonsterTurn {
-            return;
-        }
+        // Pack monsters that spot the player call out to the others
+        let mut calls: Vec<Point> = Vec::new();
+        let mut opened_door = false;
 
-        for (entity, mut viewshed, _monster, mut pos) in
-            (&entities, &mut viewshed, &monster, &mut position).join()
+        // Only monsters that have been given a turn get to act
+        for (entity, viewshed, _monster, pos, _turn, brain) in (
+            &entities,
+            &mut viewshed,
+            &monster,
+            &mut position,
+            &turns,
+            &mut brains,
+        )
+            .join()
         {
-            let mut can_act = true;
-            let is_confused = confused.get_mut(entity);
-            if let Some(i_am_confused) = is_confused {
-                i_am_confused.turns -= 1;
-                if i_am_confused.turns < 1 {
-                    confused.remove(entity);
-                }
+            let status = status_effects.get(entity);
+            if status.is_some_and(|s| s.has(StatusEffectType::Paralysis)) {
+                continue;
+            } else if status.is_some_and(|s| s.has(StatusEffectType::Confusion)) {
                 particle_builder.request(
                     pos.x,
                     pos.y,
@@ -62,13 +102,91 @@ impl<'a> System<'a> for MonsterAI {
                     rltk::to_cp437('?'),
                     200.0,
                 );
-                can_act = false;
+                continue;
             }
 
-            if can_act {
-                let distance =
-                    rltk::DistanceAlg::Pythagoras.distance2d(Point::new(pos.x, pos.y), *player_pos);
-                if distance < 1.5 {
+            let here = Point::new(pos.x, pos.y);
+            let my_idx = map.xy_idx(pos.x, pos.y);
+            let distance = rltk::DistanceAlg::Pythagoras.distance2d(here, *player_pos);
+            let sees_player = viewshed.visible_tiles.contains(&*player_pos);
+
+            if sees_player {
+                if brain.profile == AiProfile::Pack && brain.last_seen_player.is_none() {
+                    calls.push(here);
+                    if map.visible_tiles[my_idx] {
+                        if let Some(name) = names.get(entity) {
+                            log.combat(format!("{} calls for help!", &name.name));
+                        }
+                    }
+                }
+                brain.last_seen_player = Some(*player_pos);
+            }
+
+            let hurt = combat_stats.get(entity).is_some_and(|stats| {
+                (stats.hp as f32) < stats.max_hp as f32 * brain.profile.flee_below()
+            });
+            let can_shoot = sees_player
+                && ranged_weapons
+                    .get(entity)
+                    .is_some_and(|weapon| distance <= weapon.range as f32)
+                && clear_shot(&map, here, *player_pos);
+
+            let action = if let (true, Some(threat)) = (hurt, brain.last_seen_player) {
+                // Back away from where the player was, and only fight back
+                // once cornered
+                let away = if threat == *player_pos {
+                    fields.away.next_step(my_idx, &*map)
+                } else {
+                    step_away(&map, my_idx, here, threat)
+                };
+                match away {
+                    Some(exit) => Action::Step(exit),
+                    _ if distance < 1.5 => Action::Melee,
+                    _ if can_shoot => Action::Shoot,
+                    _ => Action::Wait,
+                }
+            } else if distance < 1.5 {
+                Action::Melee
+            } else if can_shoot {
+                Action::Shoot
+            } else if brain.profile == AiProfile::Stationary {
+                Action::Wait
+            } else if let Some(target) = brain.last_seen_player {
+                // Chase the player, or search where they were last seen
+                let target_idx = map.xy_idx(target.x, target.y);
+                if target_idx == my_idx {
+                    brain.last_seen_player = None;
+                    Action::Wait
+                } else if target == *player_pos {
+                    // Everyone after the player shares the same field
+                    match fields.toward.next_step(my_idx, &*map) {
+                        Some(step) => Action::Step(step),
+                        None => Action::Wait,
+                    }
+                } else {
+                    let path = rltk::a_star_search(my_idx, target_idx, &*map);
+                    if path.success && path.steps.len() > 1 {
+                        Action::Step(path.steps[1])
+                    } else {
+                        // No way through, so give up the search
+                        brain.last_seen_player = None;
+                        Action::Wait
+                    }
+                }
+            } else {
+                // Nothing to do but wander about
+                let exits = rltk::BaseMap::get_available_exits(&*map, my_idx);
+                if !exits.is_empty() && rng.roll_dice(1, 2) == 1 {
+                    let choice = rng.range(0, exits.len() as i32) as usize;
+                    Action::Step(exits[choice].0)
+                } else {
+                    Action::Wait
+                }
+            };
+
+            match action {
+                Action::Wait => {}
+                Action::Melee => {
                     wants_to_melee
                         .insert(
                             entity,
@@ -77,24 +195,89 @@ impl<'a> System<'a> for MonsterAI {
                             },
                         )
                         .expect("Unable to insert attack");
-                } else if viewshed.visible_tiles.contains(&*player_pos) {
-                    // Path to the player
-                    let path = rltk::a_star_search(
-                        map.xy_idx(pos.x, pos.y),
-                        map.xy_idx(player_pos.x, player_pos.y),
-                        &mut *map,
-                    );
-                    if path.success && path.steps.len() > 1 {
-                        let mut idx = map.xy_idx(pos.x, pos.y);
-                        map.blocked[idx] = false;
-                        pos.x = path.steps[1] as i32 % map.width;
-                        pos.y = path.steps[1] as i32 / map.width;
-                        idx = map.xy_idx(pos.x, pos.y);
-                        map.blocked[idx] = true;
+                }
+                Action::Shoot => {
+                    wants_to_shoot
+                        .insert(
+                            entity,
+                            WantsToShoot {
+                                target: *player_pos,
+                            },
+                        )
+                        .expect("Unable to insert shot");
+                }
+                Action::Step(step) => {
+                    let closed_door = map.tile_content[step]
+                        .iter()
+                        .find(|e| doors.get(**e).is_some_and(|door| !door.open))
+                        .copied();
+                    if let Some(door_entity) = closed_door {
+                        // Spend the turn opening the door in the way
+                        let door = doors.get_mut(door_entity).unwrap();
+                        door.open = true;
+                        if let Some(renderable) = renderables.get_mut(door_entity) {
+                            renderable.glyph = door.glyph();
+                        }
+                        opened_door = true;
+                    } else if !map.blocked[step] {
+                        map.blocked[my_idx] = false;
+                        pos.x = step as i32 % map.width;
+                        pos.y = step as i32 / map.width;
+                        map.blocked[step] = true;
                         viewshed.dirty = true;
+                        entity_moved
+                            .insert(entity, EntityMoved {})
+                            .expect("Unable to insert marker");
                     }
                 }
             }
         }
+
+        if opened_door {
+            visibility_system::door_changed(&mut viewshed);
+        }
+
+        // Everyone in earshot of a call heads for where the player was spotted
+        for call in calls.iter() {
+            for (pos, brain) in (&position, &mut brains).join() {
+                let distance =
+                    rltk::DistanceAlg::Pythagoras.distance2d(Point::new(pos.x, pos.y), *call);
+                if distance <= CALL_RANGE
+                    && brain.last_seen_player.is_none()
+                    && brain.profile != AiProfile::Stationary
+                {
+                    brain.last_seen_player = Some(*player_pos);
+                }
+            }
+        }
+
+        turns.clear();
     }
 }
+
+/// The exit that gets furthest from a remembered threat, if any of them
+/// is further than standing still
+fn step_away(map: &Map, my_idx: usize, here: Point, threat: Point) -> Option<usize> {
+    let current_gap = rltk::DistanceAlg::Pythagoras.distance2d(here, threat);
+    rltk::BaseMap::get_available_exits(map, my_idx)
+        .into_iter()
+        .map(|(exit, _cost)| {
+            let exit_pos = Point::new(exit as i32 % map.width, exit as i32 / map.width);
+            let gap = rltk::DistanceAlg::Pythagoras.distance2d(exit_pos, threat);
+            (exit, gap)
+        })
+        .filter(|(_exit, gap)| *gap > current_gap)
+        .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+        .map(|(exit, _gap)| exit)
+}
+
+/// True when nothing stands between the two points, so a shot would reach
+fn clear_shot(map: &Map, from: Point, to: Point) -> bool {
+    rltk::line2d(rltk::LineAlg::Bresenham, from, to)
+        .iter()
+        .filter(|point| **point != from && **point != to)
+        .all(|point| {
+            let idx = map.xy_idx(point.x, point.y);
+            !map.blocked[idx] && !map.view_blocked.contains(&idx)
+        })
+}
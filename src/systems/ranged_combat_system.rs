@@ -0,0 +1,211 @@
+extern crate specs;
+use crate::{
+    gamelog::GameLog,
+    map::Map,
+    melee_combat_system::{roll_attack, AttackRoll},
+    particle_system::ParticleBuilder,
+    Ammunition, CombatStats, DefenseBonus, EquipmentSlot, Equipped, InBackpack, Name, Position,
+    RangedWeapon, SufferDamage, TileType, WantsToShoot,
+};
+use rltk::{LineAlg, Point, RandomNumberGenerator};
+use specs::prelude::*;
+
+pub struct RangedCombatSystem {}
+
+impl<'a> System<'a> for RangedCombatSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, WantsToShoot>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, CombatStats>,
+        ReadStorage<'a, RangedWeapon>,
+        WriteStorage<'a, Ammunition>,
+        ReadStorage<'a, InBackpack>,
+        ReadStorage<'a, DefenseBonus>,
+        ReadStorage<'a, Equipped>,
+        ReadStorage<'a, Position>,
+        WriteStorage<'a, SufferDamage>,
+        ReadExpect<'a, Map>,
+        ReadExpect<'a, Entity>,
+        WriteExpect<'a, GameLog>,
+        WriteExpect<'a, ParticleBuilder>,
+        WriteExpect<'a, RandomNumberGenerator>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut wants_shoot,
+            names,
+            combat_stats,
+            ranged_weapons,
+            mut ammunition,
+            backpack,
+            defense_bonuses,
+            equipped,
+            positions,
+            mut inflict_damage,
+            map,
+            player_entity,
+            mut log,
+            mut particle_builder,
+            mut rng,
+        ) = data;
+
+        for (shooter, wants_shoot, name, stats, pos) in
+            (&entities, &wants_shoot, &names, &combat_stats, &positions).join()
+        {
+            if stats.hp < 1 {
+                continue;
+            }
+            let is_player = shooter == *player_entity;
+
+            let weapon = match ranged_weapon(shooter, &entities, &ranged_weapons, &equipped) {
+                Some(weapon) => weapon,
+                None => continue,
+            };
+
+            // Use up one piece of ammo, if the weapon needs any
+            if let Some(ammo_name) = &weapon.ammo {
+                let stack = (&entities, &backpack, &names, &mut ammunition).join().find(
+                    |(_e, pack, name, _ammo)| pack.owner == shooter && name.name == *ammo_name,
+                );
+                match stack {
+                    None => {
+                        if is_player {
+                            log.combat(format!("You have no {} left.", ammo_name));
+                        }
+                        continue;
+                    }
+                    Some((ammo_entity, _pack, _name, ammo)) => {
+                        ammo.count -= 1;
+                        if ammo.count < 1 {
+                            entities.delete(ammo_entity).expect("Delete failed");
+                        }
+                    }
+                }
+            }
+
+            // Follow the line of fire until it reaches the target, a wall or
+            // something standing in the way
+            let mut hit: Option<Entity> = None;
+            let mut hit_wall = false;
+            let start = Point::new(pos.x, pos.y);
+            for point in rltk::line2d(LineAlg::Bresenham, start, wants_shoot.target) {
+                if point == start {
+                    continue;
+                }
+                let idx = map.xy_idx(point.x, point.y);
+                if map.tiles[idx] == TileType::Wall || map.view_blocked.contains(&idx) {
+                    hit_wall = true;
+                    break;
+                }
+                particle_builder.request(
+                    point.x,
+                    point.y,
+                    rltk::RGB::named(rltk::CYAN),
+                    rltk::RGB::named(rltk::BLACK),
+                    rltk::to_cp437('*'),
+                    100.0,
+                );
+                hit = map.tile_content[idx]
+                    .iter()
+                    .find(|e| combat_stats.get(**e).is_some_and(|s| s.hp > 0))
+                    .copied();
+                if hit.is_some() || point == wants_shoot.target {
+                    break;
+                }
+            }
+
+            match hit {
+                None => {
+                    if is_player && hit_wall {
+                        log.combat("Your shot hits the wall.".to_string());
+                    } else if is_player {
+                        log.combat("Your shot hits nothing.".to_string());
+                    }
+                }
+                Some(target) => {
+                    let target_stats = combat_stats.get(target).unwrap();
+                    let target_name = names.get(target).unwrap();
+                    let mut defensive_bonus = 0;
+                    for (equipped_item, defense_bonus) in (&equipped, &defense_bonuses).join() {
+                        if equipped_item.owner == target {
+                            defensive_bonus += defense_bonus.defense;
+                        }
+                    }
+                    let roll = roll_attack(
+                        &mut rng,
+                        stats.power,
+                        target_stats.defense + defensive_bonus,
+                        (weapon.n_dice, weapon.die_type, weapon.bonus),
+                    );
+
+                    if let AttackRoll::Hit { damage, critical } = roll {
+                        if critical {
+                            log.combat(format!(
+                                "{} critically shoots {}, for {} hp!",
+                                &name.name, &target_name.name, damage
+                            ));
+                        } else {
+                            log.combat(format!(
+                                "{} shoots {}, for {} hp.",
+                                &name.name, &target_name.name, damage
+                            ));
+                        }
+                        SufferDamage::new_damage(
+                            &mut inflict_damage,
+                            target,
+                            damage,
+                            is_player,
+                            &name.name,
+                        );
+                    } else {
+                        log.combat(format!(
+                            "{} shoots at {}, but misses.",
+                            &name.name, &target_name.name
+                        ));
+                    }
+                }
+            }
+        }
+
+        wants_shoot.clear();
+    }
+}
+
+/// The ranged attack an entity has: its own, or that of the launcher it has
+/// equipped
+pub fn ranged_weapon(
+    entity: Entity,
+    entities: &Entities,
+    ranged_weapons: &ReadStorage<RangedWeapon>,
+    equipped: &ReadStorage<Equipped>,
+) -> Option<RangedWeapon> {
+    if let Some(weapon) = ranged_weapons.get(entity) {
+        return Some(weapon.clone());
+    }
+    (entities, equipped, ranged_weapons)
+        .join()
+        .find(|(_e, equipped, _weapon)| {
+            equipped.owner == entity && equipped.slot == EquipmentSlot::Ranged
+        })
+        .map(|(_e, _equipped, weapon)| weapon.clone())
+}
+
+/// Whether the entity carries the ammo the weapon needs, if it needs any
+pub fn has_ammo(
+    entity: Entity,
+    weapon: &RangedWeapon,
+    backpack: &ReadStorage<InBackpack>,
+    names: &ReadStorage<Name>,
+    ammunition: &ReadStorage<Ammunition>,
+) -> bool {
+    match &weapon.ammo {
+        None => true,
+        Some(ammo_name) => (backpack, names, ammunition)
+            .join()
+            .any(|(pack, name, _ammo)| pack.owner == entity && name.name == *ammo_name),
+    }
+}
@@ -1,55 +1,189 @@
-use crate::{components::*, map};
+use crate::{
+  components::*,
+  dungeon::MasterDungeonMap,
+  gamelog::GameLog,
+  identification::IdentificationTable,
+  initiative_system::NORMAL_SPEED,
+  map,
+  morgue::RunStats,
+  raws::RawMaster,
+  save_format::{self, Format, FormatError},
+  seed::WorldSeed,
+};
+use serde::ser::SerializeSeq;
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::{json, Value};
 use specs::error::NoError;
 use specs::prelude::*;
 use specs::saveload::{
-  DeserializeComponents, MarkedBuilder, SerializeComponents, SimpleMarker, SimpleMarkerAllocator,
+  ConvertSaveload, DeserializeComponents, MarkedBuilder, SerializeComponents, SimpleMarker,
+  SimpleMarkerAllocator,
 };
+use std::collections::HashMap;
+use std::fmt;
 use std::fs;
-use std::fs::File;
-use std::path::Path;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
 
-macro_rules! serialize_individually {
-    ($ecs:expr, $ser:expr, $data:expr, $( $type:ty),*) => {
-        $(
-        SerializeComponents::<NoError, SimpleMarker<SerializeMe>>::serialize(
-            &( $ecs.read_storage::<$type>(), ),
-            &$data.0,
-            &$data.1,
-            &mut $ser,
-        )
-        .unwrap();
-        )*
-    };
+/// Number of save slots offered on the load and save screens
+pub const SAVE_SLOTS: usize = 5;
+
+/// Where the one save went before there were slots
+const LEGACY_SAVE: &str = "./savegame.json";
+
+/// Bumped whenever the layout of a save changes, along with a migration
+/// from the previous version in `MIGRATIONS`
+pub const SAVE_VERSION: u32 = 2;
+
+/// Upgrades a save by one version
+type Migration = fn(Value) -> Result<Value, SaveError>;
+
+/// Each entry upgrades a save from the version at its index to the next
+const MIGRATIONS: &[Migration] = &[from_unversioned, ranged_damage_dice];
+
+/// Saves from before the header existed were a bare run of component
+/// arrays in this order. Frozen, so don't add new components here.
+const UNVERSIONED_COMPONENTS: &[&str] = &[
+  "Position",
+  "Renderable",
+  "Player",
+  "Viewshed",
+  "Monster",
+  "Name",
+  "BlocksTile",
+  "CombatStats",
+  "SufferDamage",
+  "WantsToMelee",
+  "Item",
+  "Consumable",
+  "Ranged",
+  "InflictsDamage",
+  "AreaOfEffect",
+  "Confusion",
+  "ProvidesHealing",
+  "InBackpack",
+  "WantsToPickupItem",
+  "WantsToUseItem",
+  "WantsToDropItem",
+  "Equippable",
+  "Equipped",
+  "MeleePowerBonus",
+  "DefenseBonus",
+  "ParticleLifetime",
+  "SerializationHelper",
+];
+
+#[derive(Serialize, Deserialize)]
+pub struct SaveHeader {
+  pub version: u32,
+  /// Names of the saved components, in the same order as their data
+  pub components: Vec<String>,
+  #[serde(default)]
+  pub metadata: SaveMetadata,
 }
 
-#[cfg(not(target_arch = "wasm32"))]
-pub fn save_game(ecs: &mut World) {
-  // Create helper
-  let mapcopy = ecs.get_mut::<map::Map>().unwrap().clone();
-  let savehelper = ecs
-    .create_entity()
-    .with(SerializationHelper { map: mapcopy })
-    .marked::<SimpleMarker<SerializeMe>>()
-    .build();
+/// How a run treats its saves, picked when the game starts
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug, Default)]
+pub enum GameMode {
+  /// Saves are checkpoints that can be returned to, even after dying
+  #[default]
+  Casual,
+  /// Loading a save deletes it, and dying deletes the run's save
+  Permadeath,
+}
 
-  // Actually serialize
-  {
-    let data = (
-      ecs.entities(),
-      ecs.read_storage::<SimpleMarker<SerializeMe>>(),
-    );
+/// A summary of the run, so the load screen can describe a save without
+/// loading it
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct SaveMetadata {
+  pub level: i32,
+  pub depth: i32,
+  pub turn: i32,
+  /// Seconds since the Unix epoch
+  pub timestamp: i64,
+  pub seed: Option<u64>,
+  #[serde(default)]
+  pub mode: GameMode,
+}
 
-    let writer = File::create("./savegame.json").unwrap();
-    let mut serializer = serde_json::Serializer::new(writer);
-    serialize_individually!(
-      ecs,
-      serializer,
-      data,
+/// What the load screen knows about one slot
+pub enum SaveSlot {
+  Empty,
+  Saved(SaveMetadata),
+  Unreadable(String),
+}
+
+/// Just the header of a save, so the rest is skipped over unread
+#[derive(Deserialize)]
+struct HeaderOnly {
+  header: SaveHeader,
+}
+
+#[derive(Deserialize)]
+struct SaveFile {
+  header: SaveHeader,
+  data: Vec<Value>,
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+  Io(std::io::Error),
+  Json(serde_json::Error),
+  Format(FormatError),
+  NewerVersion(u32),
+  UnknownComponent(String),
+  Corrupt(String),
+}
+
+impl fmt::Display for SaveError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      SaveError::Io(e) => write!(f, "the save file could not be accessed: {}", e),
+      SaveError::Json(e) => write!(f, "the save file is damaged: {}", e),
+      SaveError::Format(e) => write!(f, "{}", e),
+      SaveError::NewerVersion(version) => write!(
+        f,
+        "the save is version {}, but this game only reads up to {}",
+        version, SAVE_VERSION
+      ),
+      SaveError::UnknownComponent(name) => {
+        write!(f, "the save has data for an unknown component, {}", name)
+      }
+      SaveError::Corrupt(reason) => write!(f, "the save file is damaged: {}", reason),
+    }
+  }
+}
+
+impl From<std::io::Error> for SaveError {
+  fn from(e: std::io::Error) -> Self {
+    SaveError::Io(e)
+  }
+}
+
+impl From<serde_json::Error> for SaveError {
+  fn from(e: serde_json::Error) -> Self {
+    SaveError::Json(e)
+  }
+}
+
+impl From<FormatError> for SaveError {
+  fn from(e: FormatError) -> Self {
+    SaveError::Format(e)
+  }
+}
+
+/// Expands the given macro with every component that goes into a save,
+/// after any arguments of its own
+macro_rules! with_saved_components {
+  ($mac:ident!($($args:tt)*)) => {
+    $mac!(
+      $($args)*
       Position,
       Renderable,
       Player,
       Viewshed,
       Monster,
+      OtherLevelPosition,
       Name,
       BlocksTile,
       CombatStats,
@@ -60,7 +194,8 @@ pub fn save_game(ecs: &mut World) {
       Ranged,
       InflictsDamage,
       AreaOfEffect,
-      Confusion,
+      StatusEffects,
+      AppliesStatus,
       ProvidesHealing,
       InBackpack,
       WantsToPickupItem,
@@ -68,37 +203,297 @@ pub fn save_game(ecs: &mut World) {
       WantsToDropItem,
       Equippable,
       Equipped,
-      MeleePowerBonus,
+      MeleeWeapon,
+      RangedWeapon,
+      Ammunition,
       DefenseBonus,
       ParticleLifetime,
+      HungerClock,
+      Experience,
+      Energy,
+      Door,
+      Hidden,
+      EntryTrigger,
+      SingleActivation,
+      ProvidesFood,
+      MagicMapper,
+      ProvidesClairvoyance,
+      MonsterBrain,
       SerializationHelper
-    );
+    )
+  };
+}
+
+macro_rules! component_names {
+  ($( $type:ident),*) => {
+    vec![$( stringify!($type).to_string() ),*]
+  };
+}
+
+macro_rules! serialize_individually {
+  ($seq:expr, $ecs:expr, $( $type:ident),*) => {
+    $(
+    $seq.serialize_element(&SavedStorage::<$type> {
+      ecs: $ecs,
+      component: PhantomData,
+    })?;
+    )*
+  };
+}
+
+/// The same layout as `SaveFile`, but written straight from the world
+#[derive(Serialize)]
+struct SaveFileWriter<'a> {
+  header: SaveHeader,
+  data: SavedComponents<'a>,
+}
+
+/// Every saved component, one storage after another in the header's order
+struct SavedComponents<'a> {
+  ecs: &'a World,
+}
+
+/// One component's storage, for entities marked to be saved
+struct SavedStorage<'a, T> {
+  ecs: &'a World,
+  component: PhantomData<T>,
+}
+
+impl<'a, T> Serialize for SavedStorage<'a, T>
+where
+  T: Component + ConvertSaveload<SimpleMarker<SerializeMe>>,
+  NoError: From<T::Error>,
+{
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    SerializeComponents::<NoError, SimpleMarker<SerializeMe>>::serialize(
+      &(self.ecs.read_storage::<T>(),),
+      &self.ecs.entities(),
+      &self.ecs.read_storage::<SimpleMarker<SerializeMe>>(),
+      serializer,
+    )
+  }
+}
+
+impl<'a> Serialize for SavedComponents<'a> {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let names = with_saved_components!(component_names!());
+    let mut seq = serializer.serialize_seq(Some(names.len()))?;
+    with_saved_components!(serialize_individually!(seq, self.ecs,));
+    seq.end()
   }
+}
+
+/// Where the game keeps its files: the user's data directory, or next to
+/// the game where there isn't one
+pub fn game_data_dir() -> PathBuf {
+  dirs::data_dir().map_or_else(|| PathBuf::from("."), |dir| dir.join("roguelike"))
+}
+
+fn save_dir() -> PathBuf {
+  game_data_dir().join("saves")
+}
+
+fn slot_path(slot: usize) -> PathBuf {
+  save_dir().join(format!("slot{}.sav", slot + 1))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_game(ecs: &mut World, slot: usize) -> Result<(), SaveError> {
+  // Create helper
+  let mapcopy = ecs.get_mut::<map::Map>().unwrap().clone();
+  let seed = ecs.fetch::<WorldSeed>().seed;
+  let dungeon = (*ecs.fetch::<MasterDungeonMap>()).clone();
+  let identification = (*ecs.fetch::<IdentificationTable>()).clone();
+  let log = (*ecs.fetch::<GameLog>()).clone();
+  let mode = *ecs.fetch::<GameMode>();
+  let stats = (*ecs.fetch::<RunStats>()).clone();
+  // The generator's state can't be read out, so it is reseeded from itself
+  // and the new seed saved. Playing on and loading then roll the same dice.
+  let rng_seed = {
+    let mut rng = ecs.write_resource::<rltk::RandomNumberGenerator>();
+    let rng_seed = rng.next_u64();
+    *rng = rltk::RandomNumberGenerator::seeded(rng_seed);
+    rng_seed
+  };
+  let savehelper = ecs
+    .create_entity()
+    .with(SerializationHelper {
+      map: mapcopy,
+      seed: Some(seed),
+      dungeon,
+      identification,
+      log,
+      mode,
+      stats,
+      rng_seed: Some(rng_seed),
+    })
+    .marked::<SimpleMarker<SerializeMe>>()
+    .build();
+
+  let result = write_save(ecs, slot);
 
   // Clean up
   ecs.delete_entity(savehelper).expect("Crash on cleanup");
+  result
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_save(ecs: &World, slot: usize) -> Result<(), SaveError> {
+  let level = ecs
+    .read_storage::<Experience>()
+    .get(*ecs.fetch::<Entity>())
+    .map_or(1, |experience| experience.level);
+  let metadata = SaveMetadata {
+    level,
+    depth: ecs.fetch::<map::Map>().depth,
+    turn: ecs.fetch::<GameLog>().turn,
+    timestamp: chrono::Local::now().timestamp(),
+    seed: Some(ecs.fetch::<WorldSeed>().seed),
+    mode: *ecs.fetch::<GameMode>(),
+  };
+
+  let save = SaveFileWriter {
+    header: SaveHeader {
+      version: SAVE_VERSION,
+      components: with_saved_components!(component_names!()),
+      metadata,
+    },
+    data: SavedComponents { ecs },
+  };
+
+  fs::create_dir_all(save_dir())?;
+  save_format::write(&slot_path(slot), &save, Format::preferred())?;
+  Ok(())
+}
+
+/// Moves the save left by a version of the game from before slots into the
+/// first free one, so it shows up on the load screen. With every slot taken
+/// it stays where it is until one frees up.
+pub fn import_legacy_save() -> Result<Option<usize>, SaveError> {
+  let legacy = PathBuf::from(LEGACY_SAVE);
+  if !legacy.exists() {
+    return Ok(None);
+  }
+  let slot = match (0..SAVE_SLOTS).find(|slot| !save_exists(*slot)) {
+    Some(slot) => slot,
+    None => return Ok(None),
+  };
+  fs::create_dir_all(save_dir())?;
+  fs::copy(&legacy, slot_path(slot))?;
+  fs::remove_file(&legacy)?;
+  Ok(Some(slot))
 }
 
 pub fn does_save_exist() -> bool {
-  Path::new("./savegame.json").exists()
+  (0..SAVE_SLOTS).any(save_exists)
+}
+
+pub fn save_exists(slot: usize) -> bool {
+  slot_path(slot).exists()
+}
+
+/// Describes every slot from the header of the save in it
+pub fn save_slots() -> Vec<SaveSlot> {
+  (0..SAVE_SLOTS)
+    .map(|slot| {
+      if !slot_path(slot).exists() {
+        return SaveSlot::Empty;
+      }
+      // Saves from before the header existed have nothing to show
+      match save_format::read_part::<HeaderOnly>(&slot_path(slot)) {
+        Ok(Some(save)) if save.header.version > SAVE_VERSION => {
+          SaveSlot::Unreadable(SaveError::NewerVersion(save.header.version).to_string())
+        }
+        Ok(Some(save)) => SaveSlot::Saved(save.header.metadata),
+        Ok(None) => SaveSlot::Saved(SaveMetadata::default()),
+        Err(e) => SaveSlot::Unreadable(SaveError::from(e).to_string()),
+      }
+    })
+    .collect()
+}
+
+macro_rules! register_components {
+  ($ecs:expr, $( $type:ident),*) => {
+    $( $ecs.register::<$type>(); )*
+  };
 }
 
-macro_rules! deserialize_individually {
-  ($ecs:expr, $de:expr, $data:expr, $( $type:ty),*) => {
+macro_rules! deserialize_by_name {
+  ($ecs:expr, $data:expr, $name:expr, $value:expr, $( $type:ident),*) => {
+    match $name {
       $(
-      DeserializeComponents::<NoError, _>::deserialize(
+      stringify!($type) => DeserializeComponents::<NoError, _>::deserialize(
           &mut ( &mut $ecs.write_storage::<$type>(), ),
           & $data.0, // entities
           &mut $data.1, // marker
           &mut $data.2, // allocater
-          &mut $de,
-      )
-      .unwrap();
+          $value,
+      )?,
       )*
+      other => return Err(SaveError::UnknownComponent(other.to_string())),
+    }
   };
 }
 
-pub fn load_game(ecs: &mut World) {
+/// Fills the world with the save's entities, one component at a time
+fn deserialize_components(ecs: &mut World, save: &SaveFile) -> Result<(), SaveError> {
+  let mut d = (
+    &mut ecs.entities(),
+    &mut ecs.write_storage::<SimpleMarker<SerializeMe>>(),
+    &mut ecs.write_resource::<SimpleMarkerAllocator<SerializeMe>>(),
+  );
+  for (name, value) in save.header.components.iter().zip(save.data.iter()) {
+    with_saved_components!(deserialize_by_name!(ecs, d, name.as_str(), value,));
+  }
+  Ok(())
+}
+
+pub fn load_game(ecs: &mut World, slot: usize) -> Result<(), SaveError> {
+  load_save(ecs, read_save(&slot_path(slot))?)
+}
+
+/// Replaces the world with the contents of a save, after bringing it up to
+/// date
+fn load_save(ecs: &mut World, save: Value) -> Result<(), SaveError> {
+  let save: SaveFile = serde_json::from_value(migrate(save)?)?;
+  let known = with_saved_components!(component_names!());
+  if let Some(unknown) = save
+    .header
+    .components
+    .iter()
+    .find(|name| !known.contains(name))
+  {
+    return Err(SaveError::UnknownComponent(unknown.clone()));
+  }
+  if save.header.components.len() != save.data.len() {
+    return Err(SaveError::Corrupt(format!(
+      "the header lists {} components, but there is data for {}",
+      save.header.components.len(),
+      save.data.len()
+    )));
+  }
+  if !save
+    .header
+    .components
+    .iter()
+    .any(|name| name == "SerializationHelper")
+  {
+    return Err(SaveError::Corrupt("there is no map".to_string()));
+  }
+
+  // Load into a scratch world first, so a save that only turns out to be
+  // bad halfway through leaves the game as it was
+  {
+    let mut scratch = World::new();
+    scratch.register::<SimpleMarker<SerializeMe>>();
+    scratch.insert(SimpleMarkerAllocator::<SerializeMe>::new());
+    with_saved_components!(register_components!(scratch,));
+    deserialize_components(&mut scratch, &save)?;
+    if scratch.read_storage::<SerializationHelper>().is_empty() {
+      return Err(SaveError::Corrupt("there is no map".to_string()));
+    }
+  }
+
   {
     // Delete everything
     let mut to_delete = Vec::new();
@@ -109,50 +504,7 @@ pub fn load_game(ecs: &mut World) {
       ecs.delete_entity(*del).expect("Deletion failed");
     }
   }
-
-  let data = fs::read_to_string("./savegame.json").unwrap();
-  let mut de = serde_json::Deserializer::from_str(&data);
-
-  {
-    let mut d = (
-      &mut ecs.entities(),
-      &mut ecs.write_storage::<SimpleMarker<SerializeMe>>(),
-      &mut ecs.write_resource::<SimpleMarkerAllocator<SerializeMe>>(),
-    );
-
-    deserialize_individually!(
-      ecs,
-      de,
-      d,
-      Position,
-      Renderable,
-      Player,
-      Viewshed,
-      Monster,
-      Name,
-      BlocksTile,
-      CombatStats,
-      SufferDamage,
-      WantsToMelee,
-      Item,
-      Consumable,
-      Ranged,
-      InflictsDamage,
-      AreaOfEffect,
-      Confusion,
-      ProvidesHealing,
-      InBackpack,
-      WantsToPickupItem,
-      WantsToUseItem,
-      WantsToDropItem,
-      Equippable,
-      Equipped,
-      MeleePowerBonus,
-      DefenseBonus,
-      ParticleLifetime,
-      SerializationHelper
-    );
-  }
+  deserialize_components(ecs, &save)?;
 
   let mut deleteme: Option<Entity> = None;
   {
@@ -164,6 +516,29 @@ pub fn load_game(ecs: &mut World) {
       let mut worldmap = ecs.write_resource::<map::Map>();
       *worldmap = h.map.clone();
       worldmap.tile_content = vec![Vec::new(); map::MAPCOUNT];
+      // Saves from before runs kept their seed are given a fresh one, and
+      // with it unidentified names for the rest of the run
+      let (seed, identification) = match h.seed {
+        Some(seed) => (WorldSeed::new(seed), h.identification.clone()),
+        None => {
+          let seed = WorldSeed::random();
+          let identification =
+            IdentificationTable::new(&ecs.fetch::<RawMaster>(), &mut seed.identification_rng());
+          (seed, identification)
+        }
+      };
+      // Pick up the dice where they were left. Saves from before that was
+      // kept start over from where the level did.
+      *ecs.write_resource::<rltk::RandomNumberGenerator>() = match h.rng_seed {
+        Some(rng_seed) => rltk::RandomNumberGenerator::seeded(rng_seed),
+        None => seed.level_rng(worldmap.depth),
+      };
+      *ecs.write_resource::<WorldSeed>() = seed;
+      *ecs.write_resource::<MasterDungeonMap>() = h.dungeon.clone();
+      *ecs.write_resource::<IdentificationTable>() = identification;
+      *ecs.write_resource::<GameLog>() = h.log.clone();
+      *ecs.write_resource::<GameMode>() = h.mode;
+      *ecs.write_resource::<RunStats>() = h.stats.clone();
       deleteme = Some(e);
     }
     for (e, _p, pos) in (&entities, &player, &position).join() {
@@ -173,13 +548,306 @@ pub fn load_game(ecs: &mut World) {
       *player_resource = e;
     }
   }
-  ecs
-    .delete_entity(deleteme.unwrap())
-    .expect("Unable to delete helper");
+  let helper = deleteme.ok_or_else(|| SaveError::Corrupt("there is no map".to_string()))?;
+  ecs.delete_entity(helper).expect("Unable to delete helper");
+  Ok(())
 }
 
-pub fn delete_save() {
-  if Path::new("./savegame.json").exists() {
-    std::fs::remove_file("./savegame.json").expect("Unable to delete file");
+/// Reads the save as a loose JSON value, whatever format it was written
+/// in, so migrations can work on it before it has to fit the current layout
+fn read_save(path: &Path) -> Result<Value, SaveError> {
+  let save = save_format::read(path)?;
+  if save.get("header").is_some() {
+    Ok(save)
+  } else {
+    Ok(json!({
+      "header": { "version": 0, "components": [] },
+      "data": save,
+    }))
+  }
+}
+
+/// Runs every migration from the save's version up to the current one
+fn migrate(mut save: Value) -> Result<Value, SaveError> {
+  let version = save
+    .pointer("/header/version")
+    .and_then(Value::as_u64)
+    .ok_or_else(|| SaveError::Corrupt("the header has no version".to_string()))?
+    as u32;
+  if version > SAVE_VERSION {
+    return Err(SaveError::NewerVersion(version));
+  }
+  for migration in MIGRATIONS[version as usize..].iter() {
+    save = migration(save)?;
+  }
+  Ok(save)
+}
+
+/// Version 0 to 1: names the components of a save written before the
+/// header existed, and brings them up to date. Confusion became a status
+/// effect, melee power bonuses became weapon dice, and the player and
+/// monsters are given the components everything that acts now needs.
+fn from_unversioned(save: Value) -> Result<Value, SaveError> {
+  let data = match save["data"].as_array() {
+    Some(data) if data.len() == UNVERSIONED_COMPONENTS.len() => data,
+    found => {
+      return Err(SaveError::Corrupt(format!(
+        "expected {} components in an old save, found {}",
+        UNVERSIONED_COMPONENTS.len(),
+        found.map_or(0, Vec::len)
+      )))
+    }
+  };
+  let mut old: HashMap<&str, Vec<(Value, Value)>> = HashMap::new();
+  for (name, storage) in UNVERSIONED_COMPONENTS.iter().zip(data) {
+    let entries = storage
+      .as_array()
+      .ok_or_else(|| SaveError::Corrupt(format!("the {} data is not a list", name)))?
+      .iter()
+      .filter_map(|entry| match entry["components"][0] {
+        Value::Null => None,
+        ref component => Some((entry["marker"].clone(), component.clone())),
+      })
+      .collect();
+    old.insert(name, entries);
+  }
+  let markers = |name: &str| -> Vec<Value> { old[name].iter().map(|(m, _)| m.clone()).collect() };
+  let items = markers("Item");
+
+  let mut components: Vec<(&str, Vec<(Value, Value)>)> = Vec::new();
+  for name in UNVERSIONED_COMPONENTS.iter() {
+    match *name {
+      "Confusion" | "MeleePowerBonus" => {}
+      "SufferDamage" => components.push((
+        name,
+        old[name]
+          .iter()
+          .map(|(marker, damage)| {
+            let amount = damage["amount"]
+              .as_array()
+              .map_or_else(Vec::new, |amounts| {
+                amounts
+                  .iter()
+                  .map(|amount| json!([amount, false, ""]))
+                  .collect()
+              });
+            (marker.clone(), json!({ "amount": amount }))
+          })
+          .collect(),
+      )),
+      _ => components.push((name, old[name].clone())),
+    }
+  }
+
+  // A confusion scroll now applies the effect, a confused monster has it
+  let confusion =
+    |turns: &Value| json!({ "effects": [{ "kind": "Confusion", "turns": turns, "potency": 0 }] });
+  let (applies, suffers): (Vec<_>, Vec<_>) = old["Confusion"]
+    .iter()
+    .map(|(marker, confusion_data)| (marker.clone(), confusion(&confusion_data["turns"])))
+    .partition(|(marker, _)| items.contains(marker));
+  components.push(("AppliesStatus", applies));
+  components.push(("StatusEffects", suffers));
+  components.push((
+    "MeleeWeapon",
+    old["MeleePowerBonus"]
+      .iter()
+      .map(|(marker, bonus)| {
+        // The bonus went on top of fighting bare-handed, which now rolls
+        // the 1d4 of UNARMED in melee_combat_system
+        let weapon = json!({ "n_dice": 1, "die_type": 4, "bonus": bonus["power"] });
+        (marker.clone(), weapon)
+      })
+      .collect(),
+  ));
+
+  let player = markers("Player");
+  let monsters = markers("Monster");
+  let with = |markers: &[Value], component: Value| -> Vec<(Value, Value)> {
+    markers
+      .iter()
+      .map(|marker| (marker.clone(), component.clone()))
+      .collect()
+  };
+  let actors: Vec<Value> = player.iter().chain(monsters.iter()).cloned().collect();
+  components.push((
+    "Energy",
+    with(&actors, json!({ "speed": NORMAL_SPEED, "energy": 0 })),
+  ));
+  components.push((
+    "MonsterBrain",
+    with(
+      &monsters,
+      json!({ "profile": "Aggressive", "last_seen_player": null }),
+    ),
+  ));
+  components.push((
+    "HungerClock",
+    with(&player, json!({ "state": "WellFed", "duration": 20 })),
+  ));
+  components.push(("Experience", with(&player, json!({ "level": 1, "xp": 0 }))));
+
+  let names: Vec<&str> = components.iter().map(|(name, _)| *name).collect();
+  let data: Vec<Value> = components
+    .into_iter()
+    .map(|(_, entries)| {
+      entries
+        .into_iter()
+        .map(|(marker, component)| json!({ "marker": marker, "components": [component] }))
+        .collect()
+    })
+    .collect();
+  Ok(json!({
+    "header": { "version": 1, "components": names },
+    "data": data,
+  }))
+}
+
+/// Version 1 to 2: ranged weapons roll dice for damage rather than doing a
+/// flat amount. The old amount becomes the most the die can roll.
+fn ranged_damage_dice(mut save: Value) -> Result<Value, SaveError> {
+  let index = save["header"]["components"]
+    .as_array()
+    .and_then(|names| names.iter().position(|name| name == "RangedWeapon"));
+  if let Some(storage) = index.and_then(|index| save["data"][index].as_array_mut()) {
+    for entry in storage.iter_mut() {
+      let weapon = &mut entry["components"][0];
+      if let Some(damage) = weapon.as_object_mut().and_then(|w| w.remove("damage")) {
+        weapon["n_dice"] = json!(1);
+        weapon["die_type"] = damage;
+        weapon["bonus"] = json!(0);
+      }
+    }
+  }
+  save["header"]["version"] = json!(2);
+  Ok(save)
+}
+
+pub fn delete_save(slot: usize) {
+  let path = slot_path(slot);
+  if path.exists() {
+    std::fs::remove_file(path).expect("Unable to delete file");
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::State;
+
+  /// A save written by the game before saves had a header
+  fn unversioned_save() -> Value {
+    let fixture =
+      Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/unversioned_save.json");
+    read_save(&fixture).expect("Unable to read the fixture")
+  }
+
+  fn named(ecs: &World, name: &str) -> Vec<Entity> {
+    let names = ecs.read_storage::<Name>();
+    (&ecs.entities(), &names)
+      .join()
+      .filter(|(_e, n)| n.name == name)
+      .map(|(e, _n)| e)
+      .collect()
+  }
+
+  #[test]
+  fn unversioned_saves_load_into_the_current_layout() {
+    let mut gs = State::new(Some(7));
+    load_save(&mut gs.ecs, unversioned_save()).expect("Unable to load the save");
+    let ecs = &gs.ecs;
+
+    let player = *ecs.fetch::<Entity>();
+    assert_eq!(named(ecs, "Player"), vec![player]);
+    assert!(ecs.read_storage::<Energy>().contains(player));
+    assert!(ecs.read_storage::<HungerClock>().contains(player));
+    assert_eq!(
+      ecs.read_storage::<Experience>().get(player).unwrap().level,
+      1
+    );
+
+    // The confused goblin is still confused, with its pending damage
+    let goblin = named(ecs, "Goblin")[0];
+    let statuses = ecs.read_storage::<StatusEffects>();
+    assert!(statuses
+      .get(goblin)
+      .unwrap()
+      .has(StatusEffectType::Confusion));
+    assert_eq!(
+      ecs
+        .read_storage::<SufferDamage>()
+        .get(goblin)
+        .unwrap()
+        .amount
+        .len(),
+      1
+    );
+    assert_eq!(
+      ecs
+        .read_storage::<MonsterBrain>()
+        .get(goblin)
+        .unwrap()
+        .profile,
+      AiProfile::Aggressive
+    );
+
+    let scroll = named(ecs, "Confusion Scroll")[0];
+    assert!(ecs.read_storage::<AppliesStatus>().contains(scroll));
+    assert!(!statuses.contains(scroll));
+
+    let weapons = ecs.read_storage::<MeleeWeapon>();
+    let dagger = weapons.get(named(ecs, "Dagger")[0]).unwrap();
+    assert_eq!((dagger.n_dice, dagger.die_type, dagger.bonus), (1, 4, 2));
+
+    // With no seed in the save, the run is given unidentified names afresh
+    let identification = ecs.fetch::<IdentificationTable>();
+    assert_ne!(
+      identification.display_name("Confusion Scroll"),
+      "Confusion Scroll"
+    );
+    assert!(ecs.read_storage::<SerializationHelper>().is_empty());
+  }
+
+  #[test]
+  fn saves_from_a_newer_version_are_refused() {
+    let save = json!({
+      "header": { "version": SAVE_VERSION + 1, "components": [] },
+      "data": [],
+    });
+    let mut gs = State::new(Some(7));
+    match load_save(&mut gs.ecs, save) {
+      Err(SaveError::NewerVersion(version)) => assert_eq!(version, SAVE_VERSION + 1),
+      _ => panic!("a save from a newer version was loaded"),
+    }
+  }
+
+  #[test]
+  fn a_save_that_fails_partway_leaves_the_game_as_it_was() {
+    let mut save = migrate(unversioned_save()).unwrap();
+    // Damage the last component to be read, after everything else is in
+    let last = save["data"].as_array().unwrap().len() - 1;
+    save["data"][last][0]["components"][0] = json!("not a component");
+
+    let mut gs = State::new(Some(7));
+    let player = *gs.ecs.fetch::<Entity>();
+    let entities_before = gs.ecs.entities().join().count();
+    assert!(load_save(&mut gs.ecs, save).is_err());
+    assert!(gs.ecs.is_alive(player));
+    assert_eq!(gs.ecs.entities().join().count(), entities_before);
+    assert_eq!(*gs.ecs.fetch::<Entity>(), player);
+  }
+
+  #[test]
+  fn flat_ranged_damage_becomes_a_die() {
+    let save = json!({
+      "header": { "version": 1, "components": ["RangedWeapon"] },
+      "data": [[{ "marker": [0], "components": [{ "range": 6, "damage": 5, "ammo": null }] }]],
+    });
+    let save = migrate(save).unwrap();
+    assert_eq!(save["header"]["version"], SAVE_VERSION);
+    assert_eq!(
+      save["data"][0][0]["components"][0],
+      json!({ "range": 6, "n_dice": 1, "die_type": 5, "bonus": 0, "ammo": null })
+    );
   }
 }
@@ -0,0 +1,205 @@
+extern crate specs;
+use crate::{
+    gamelog::GameLog, initiative_system::GameClock, CombatStats, Position, StatusEffectType,
+    StatusEffects, SufferDamage, Viewshed,
+};
+use specs::prelude::*;
+
+pub struct StatusEffectSystem {}
+
+impl<'a> System<'a> for StatusEffectSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, StatusEffects>,
+        WriteStorage<'a, CombatStats>,
+        WriteStorage<'a, SufferDamage>,
+        WriteStorage<'a, Viewshed>,
+        ReadStorage<'a, Position>,
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, GameClock>,
+        WriteExpect<'a, GameLog>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut status_effects,
+            mut combat_stats,
+            mut inflict_damage,
+            mut viewsheds,
+            positions,
+            player_entity,
+            clock,
+            mut log,
+        ) = data;
+
+        // Effects tick once a round of game time, whoever's turn it is
+        if !clock.round_ended {
+            return;
+        }
+
+        // Only those on the current level, the rest are frozen until the
+        // player comes back
+        let mut finished = Vec::new();
+        for (entity, status, _pos) in (&entities, &mut status_effects, &positions).join() {
+            let is_player = entity == *player_entity;
+            for effect in status.effects.iter_mut() {
+                match effect.kind {
+                    StatusEffectType::Poison => {
+                        SufferDamage::new_damage(
+                            &mut inflict_damage,
+                            entity,
+                            effect.potency,
+                            false,
+                            "poison",
+                        );
+                        if is_player {
+                            log.combat(format!("You take {} poison damage.", effect.potency));
+                        }
+                    }
+                    StatusEffectType::Regeneration => {
+                        if let Some(stats) = combat_stats.get_mut(entity) {
+                            stats.hp = i32::min(stats.hp + effect.potency, stats.max_hp);
+                        }
+                    }
+                    _ => {}
+                }
+                effect.turns -= 1;
+            }
+
+            for effect in status.effects.iter().filter(|e| e.turns < 1) {
+                if is_player {
+                    log.system(format!(
+                        "You are no longer {}.",
+                        effect.kind.name().to_lowercase()
+                    ));
+                }
+                // Let the entity see normally again
+                if effect.kind == StatusEffectType::Blindness {
+                    if let Some(viewshed) = viewsheds.get_mut(entity) {
+                        viewshed.dirty = true;
+                    }
+                }
+            }
+            status.effects.retain(|e| e.turns > 0);
+            if status.effects.is_empty() {
+                finished.push(entity);
+            }
+        }
+
+        for entity in finished {
+            status_effects.remove(entity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        initiative_system::{InitiativeSystem, NORMAL_SPEED},
+        Energy, MyTurn, RunState, StatusEffect,
+    };
+
+    fn world() -> World {
+        let mut ecs = World::new();
+        ecs.register::<StatusEffects>();
+        ecs.register::<CombatStats>();
+        ecs.register::<SufferDamage>();
+        ecs.register::<Viewshed>();
+        ecs.register::<Position>();
+        ecs.register::<Energy>();
+        ecs.register::<MyTurn>();
+        ecs.insert(GameClock::default());
+        ecs.insert(GameLog::new(String::new()));
+        ecs.insert(RunState::Ticking);
+        let player = ecs.create_entity().build();
+        ecs.insert(player);
+        ecs
+    }
+
+    fn effect(kind: StatusEffectType, turns: i32, potency: i32) -> StatusEffect {
+        StatusEffect {
+            kind,
+            turns,
+            potency,
+        }
+    }
+
+    fn apply(ecs: &mut World, victim: Entity, effect: StatusEffect) {
+        StatusEffects::apply(&mut ecs.write_storage::<StatusEffects>(), victim, effect);
+    }
+
+    #[test]
+    fn poison_stacks_into_one_effect() {
+        let mut ecs = world();
+        let victim = ecs.create_entity().build();
+        apply(&mut ecs, victim, effect(StatusEffectType::Poison, 3, 1));
+        apply(&mut ecs, victim, effect(StatusEffectType::Poison, 5, 2));
+        apply(
+            &mut ecs,
+            victim,
+            effect(StatusEffectType::Regeneration, 4, 3),
+        );
+        apply(
+            &mut ecs,
+            victim,
+            effect(StatusEffectType::Regeneration, 2, 1),
+        );
+
+        let statuses = ecs.read_storage::<StatusEffects>();
+        let effects = &statuses.get(victim).unwrap().effects;
+        assert_eq!(effects.len(), 2);
+        assert_eq!((effects[0].turns, effects[0].potency), (5, 3));
+        assert_eq!((effects[1].turns, effects[1].potency), (4, 3));
+    }
+
+    #[test]
+    fn haste_and_slow_cancel_out() {
+        let mut ecs = world();
+        let victim = ecs.create_entity().build();
+        apply(&mut ecs, victim, effect(StatusEffectType::Haste, 5, 0));
+        apply(&mut ecs, victim, effect(StatusEffectType::Slow, 5, 0));
+        assert!(ecs
+            .read_storage::<StatusEffects>()
+            .get(victim)
+            .unwrap()
+            .effects
+            .is_empty());
+    }
+
+    /// How many ticks of game time the effect lasts on something moving at
+    /// the given speed
+    fn ticks_to_wear_off(kind: StatusEffectType, speed: i32) -> i32 {
+        let mut ecs = world();
+        let victim = ecs
+            .create_entity()
+            .with(Position { x: 0, y: 0 })
+            .with(Energy { speed, energy: 0 })
+            .build();
+        apply(&mut ecs, victim, effect(kind, 3, 0));
+        let mut ticks = 0;
+        while ecs.read_storage::<StatusEffects>().contains(victim) {
+            InitiativeSystem {}.run_now(&ecs);
+            StatusEffectSystem {}.run_now(&ecs);
+            ecs.write_storage::<MyTurn>().clear();
+            ticks += 1;
+        }
+        ticks
+    }
+
+    #[test]
+    fn effects_wear_off_on_game_time() {
+        let normal = ticks_to_wear_off(StatusEffectType::Blindness, NORMAL_SPEED);
+        assert_eq!(normal, 30);
+        assert_eq!(
+            ticks_to_wear_off(StatusEffectType::Haste, NORMAL_SPEED),
+            normal
+        );
+        assert_eq!(
+            ticks_to_wear_off(StatusEffectType::Slow, NORMAL_SPEED * 2),
+            normal
+        );
+    }
+}
@@ -0,0 +1,102 @@
+extern crate specs;
+use crate::{
+    gamelog::GameLog, map::Map, particle_system::ParticleBuilder, AppliesStatus, EntityMoved,
+    EntryTrigger, Hidden, InflictsDamage, Name, Position, SingleActivation, StatusEffects,
+    SufferDamage,
+};
+use specs::prelude::*;
+
+/// Sets off traps and other entry triggers under anything that moved
+pub struct TriggerSystem {}
+
+impl<'a> System<'a> for TriggerSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        ReadExpect<'a, Map>,
+        WriteStorage<'a, EntityMoved>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, EntryTrigger>,
+        WriteStorage<'a, Hidden>,
+        ReadStorage<'a, Name>,
+        Entities<'a>,
+        WriteExpect<'a, GameLog>,
+        ReadStorage<'a, InflictsDamage>,
+        ReadStorage<'a, AppliesStatus>,
+        ReadStorage<'a, SingleActivation>,
+        WriteStorage<'a, SufferDamage>,
+        WriteStorage<'a, StatusEffects>,
+        WriteExpect<'a, ParticleBuilder>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            map,
+            mut entity_moved,
+            position,
+            entry_trigger,
+            mut hidden,
+            names,
+            entities,
+            mut log,
+            inflicts_damage,
+            applies_status,
+            single_activation,
+            mut inflict_damage,
+            mut status_effects,
+            mut particle_builder,
+        ) = data;
+
+        let mut used_up: Vec<Entity> = Vec::new();
+        for (entity, _moved, pos) in (&entities, &entity_moved, &position).join() {
+            let idx = map.xy_idx(pos.x, pos.y);
+            for trap in map.tile_content[idx].iter() {
+                if *trap == entity || entry_trigger.get(*trap).is_none() {
+                    continue;
+                }
+
+                // A trap that goes off in sight is no longer a secret
+                if map.visible_tiles[idx] {
+                    hidden.remove(*trap);
+                    if let Some(name) = names.get(*trap) {
+                        log.combat(format!("{} triggers!", &name.name));
+                    }
+                }
+
+                if let Some(damage) = inflicts_damage.get(*trap) {
+                    particle_builder.request(
+                        pos.x,
+                        pos.y,
+                        rltk::RGB::named(rltk::ORANGE),
+                        rltk::RGB::named(rltk::BLACK),
+                        rltk::to_cp437('‼'),
+                        200.0,
+                    );
+                    let cause = names.get(*trap).map_or("a trap", |name| name.name.as_str());
+                    SufferDamage::new_damage(
+                        &mut inflict_damage,
+                        entity,
+                        damage.damage,
+                        false,
+                        cause,
+                    );
+                }
+
+                if let Some(applies) = applies_status.get(*trap) {
+                    for effect in applies.effects.iter() {
+                        StatusEffects::apply(&mut status_effects, entity, *effect);
+                    }
+                }
+
+                if single_activation.get(*trap).is_some() {
+                    used_up.push(*trap);
+                }
+            }
+        }
+
+        for trap in used_up.iter() {
+            entities.delete(*trap).expect("Unable to delete trap");
+        }
+
+        entity_moved.clear();
+    }
+}
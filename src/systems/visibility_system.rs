@@ -1,5 +1,5 @@
 extern crate specs;
-use crate::{map::Map, Player, Position, Viewshed};
+use crate::{map::Map, Player, Position, StatusEffectType, StatusEffects, Viewshed};
 use specs::prelude::*;
 extern crate rltk;
 use rltk::{field_of_view, Point};
@@ -13,14 +13,20 @@ impl<'a> System<'a> for VisibilitySystem {
         WriteStorage<'a, Viewshed>,
         ReadStorage<'a, Position>,
         ReadStorage<'a, Player>,
+        ReadStorage<'a, StatusEffects>,
     );
 
-    fn run(&mut self, (mut map, entities, mut viewshed, pos, player): Self::SystemData) {
+    fn run(&mut self, (mut map, entities, mut viewshed, pos, player, status): Self::SystemData) {
         for (ent, viewshed, pos) in (&entities, &mut viewshed, &pos).join() {
-            if viewshed.dirty {
+            // Blind entities only make out what is right next to them, and
+            // are looked at every turn so blindness takes hold straight away
+            let blind = status
+                .get(ent)
+                .is_some_and(|s| s.has(StatusEffectType::Blindness));
+            if viewshed.dirty || blind {
                 viewshed.dirty = false;
-                viewshed.visible_tiles =
-                    field_of_view(Point::new(pos.x, pos.y), viewshed.range, &*map);
+                let range = if blind { 1 } else { viewshed.range };
+                viewshed.visible_tiles = field_of_view(Point::new(pos.x, pos.y), range, &*map);
                 viewshed
                     .visible_tiles
                     .retain(|p| p.x > 0 && p.x < map.width - 1 && p.y > 0 && p.y < map.height - 1);
@@ -41,3 +47,11 @@ impl<'a> System<'a> for VisibilitySystem {
         }
     }
 }
+
+/// Opening or closing a door changes what everyone nearby can see, so every
+/// viewshed has to be worked out again
+pub fn door_changed(viewsheds: &mut WriteStorage<Viewshed>) {
+    for viewshed in viewsheds.join() {
+        viewshed.dirty = true;
+    }
+}
@@ -0,0 +1,80 @@
+//! Binary saves have to come back exactly as they went in, including maps
+//! keyed by numbers such as the dungeon's levels by depth.
+//!
+//! Run with `cargo test --features binary-saves`.
+
+#![cfg(feature = "binary-saves")]
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[allow(dead_code)]
+#[path = "../src/save_format.rs"]
+mod save_format;
+use save_format::{Encoding, Format};
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Level {
+    depth: i32,
+    tiles: Vec<bool>,
+    brightness: f32,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Save {
+    seed: u64,
+    levels: HashMap<i32, Level>,
+    names: HashMap<String, String>,
+    last_seen: Option<(i32, i32)>,
+}
+
+fn sample_save() -> Save {
+    let mut levels = HashMap::new();
+    for depth in [-1, 1, 2].iter() {
+        levels.insert(
+            *depth,
+            Level {
+                depth: *depth,
+                tiles: vec![true, false, true],
+                brightness: 0.5,
+            },
+        );
+    }
+    let mut names = HashMap::new();
+    names.insert("Health Potion".to_string(), "murky potion".to_string());
+    Save {
+        seed: u64::MAX,
+        levels,
+        names,
+        last_seen: None,
+    }
+}
+
+fn round_trip(format: Format, file_name: &str) {
+    let path = std::env::temp_dir().join(file_name);
+    let save = sample_save();
+    save_format::write(&path, &save, format).unwrap();
+    let value = save_format::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let loaded: Save = serde_json::from_value(value).unwrap();
+    assert_eq!(loaded, save);
+}
+
+#[test]
+fn binary_save_round_trips() {
+    let format = Format {
+        encoding: Encoding::Binary,
+        compressed: false,
+    };
+    round_trip(format, "roguelike-binary-round-trip.sav");
+}
+
+#[cfg(feature = "compressed-saves")]
+#[test]
+fn compressed_binary_save_round_trips() {
+    let format = Format {
+        encoding: Encoding::Binary,
+        compressed: true,
+    };
+    round_trip(format, "roguelike-compressed-round-trip.sav");
+}